@@ -3,22 +3,321 @@
  * This file is part of Cargo Graphmod.
  * SPDX-License-Identifier: GPL-3.0-only
  */
-use std::path::Path;
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    dependencies_graph::DependenciesGraph,
+    cfg::CfgSet,
+    configuration::{
+        Configuration, Granularity, MetadataSource, ParserKind, TargetKind, TargetSelection,
+    },
+    crate_targets,
+    dependencies_graph::{DependenciesGraph, Reexports},
     dependencies_processor::rust_processor::target_computer::RustDependencyProcessor,
     files_reader,
     formatter::{dot_formatter::DotFormatter, Formatter},
-    parser::rust_parser::RustParser,
+    manifest::{Manifest, MANIFEST},
+    metadata::Metadata,
+    package::Package,
+    parser::{rust_parser::RustParser, syn_parser::SynParser},
 };
 
-pub fn run_app(directory: &str, pkg_name: &str) -> String {
-    let path = Path::new(directory);
-    let skip_length = path.iter().count();
+/**
+ * The crates of the sysroot, which can be named without being dependencies.
+ */
+const SYSROOT_CRATES: [&str; 4] = ["std", "core", "alloc", "proc_macro"];
+
+/**
+ * Reads the output of `cargo metadata` if selected; it is reported and left out when it cannot be read.
+ */
+fn read_metadata(directory: &Path, configuration: &Configuration) -> Option<Metadata> {
+    let metadata = match configuration.metadata.as_ref()? {
+        MetadataSource::Cargo => Metadata::run(directory),
+        MetadataSource::File(path) => Metadata::read(path),
+    };
+    match metadata {
+        Ok(metadata) => Some(metadata),
+        Err(error) => {
+            eprintln!(
+                "Warning: unable to read the metadata ({}); it has been ignored.",
+                error
+            );
+            None
+        }
+    }
+}
+
+/**
+ * Reads the modules of a crate of the package from its roots, each keyed as given,
+ * then drops the dependencies which are not to be drawn.
+ * The `extern crate` aliases of the crate are added to those of its package.
+ */
+fn read_crate(
+    roots: &[(PathBuf, Vec<String>)],
+    package: &mut Package,
+    cfgs: &CfgSet,
+    configuration: &Configuration,
+    visited: &mut Set<PathBuf>,
+) -> Result<DependenciesGraph> {
+    let mut trie = DependenciesGraph::new();
+    match configuration.parser {
+        ParserKind::Regex => files_reader::build_dependencies_trie::<RustParser>(
+            roots,
+            &mut trie,
+            &mut package.aliases,
+            configuration.tests,
+            cfgs,
+            visited,
+        ),
+        ParserKind::Syn => files_reader::build_dependencies_trie::<SynParser>(
+            roots,
+            &mut trie,
+            &mut package.aliases,
+            configuration.tests,
+            cfgs,
+            visited,
+        ),
+    }?;
+    if !configuration.tests {
+        trie.for_each_value_mut(&mut |module| {
+            module
+                .dependencies
+                .retain(|dependency| !dependency.test_only);
+            for dependencies in module.items.values_mut() {
+                dependencies.retain(|dependency| !dependency.test_only);
+            }
+        });
+    }
+    match configuration.granularity {
+        Granularity::Module => trie.for_each_value_mut(&mut |module| module.items.clear()),
+        // the imports of a module show through the items using them
+        Granularity::Item => trie.for_each_value_mut(&mut |module| {
+            module
+                .dependencies
+                .retain(|dependency| dependency.reexported)
+        }),
+    }
+    if !configuration.spans {
+        trie.for_each_value_mut(&mut |module| {
+            let items = module.items.values_mut().flatten();
+            for dependency in module.dependencies.iter_mut().chain(items) {
+                dependency.span = None;
+            }
+        });
+    }
+    if configuration.follow_reexports {
+        package.reexports = Some(Reexports::new(&trie));
+    }
+    Ok(trie)
+}
+
+/**
+ * Reads the modules of the package in `directory`, and what is known about the package.
+ * The crate name and the targets are read from the manifest; `pkg_name` is used only when there is none.
+ * Unless the targets are selected, the library and the binaries are read as a single crate.
+ * When several selected targets are drawn, each one is nested under its key like the members of a workspace;
+ * only the library can be imported by name, by the other targets.
+ */
+fn read_package(
+    directory: &Path,
+    pkg_name: &str,
+    configuration: &Configuration,
+    workspace: Option<&Manifest>,
+    metadata: Option<&Metadata>,
+) -> Result<(DependenciesGraph, Package)> {
+    let mut package = Package::new(pkg_name);
+    let mut package_name = pkg_name.to_string();
+    let manifest = Manifest::read(&directory.join(MANIFEST));
+    if let Some(manifest) = &manifest {
+        package.aliases = manifest.renamed_dependencies();
+        // a package drawn on its own may still inherit its edition from its workspace
+        let enclosing_workspace = workspace
+            .is_none()
+            .then(|| Manifest::enclosing_workspace(directory))
+            .flatten();
+        package.edition = manifest.edition(workspace.or(enclosing_workspace.as_ref()));
+        if let Some(name) = manifest.library_name() {
+            package.name = name;
+        }
+        if let Some(name) = manifest.package_name() {
+            package_name = name;
+        }
+    }
+    let extern_crates = |dev| {
+        metadata
+            .and_then(|metadata| metadata.extern_crates(&package_name, dev))
+            .map(|mut crates| {
+                crates.extend(SYSROOT_CRATES.map(String::from));
+                crates
+            })
+    };
+    package.extern_crates = extern_crates(false);
+    let dev_crates = extern_crates(true);
+    package.dev_crates = dev_crates.clone().unwrap_or_default();
+    let cfgs = CfgSet {
+        features: configuration
+            .features
+            .as_ref()
+            .map(|selection| match &manifest {
+                Some(manifest) => manifest.enabled_features(selection),
+                None => selection.features.iter().cloned().collect(),
+            }),
+        platform: configuration.platform.platform(),
+    };
+    let default_selection = TargetSelection::default();
+    let kinds = &configuration
+        .targets
+        .as_ref()
+        .unwrap_or(&default_selection)
+        .0;
+    let targets =
+        crate_targets::find_targets(directory, &package.name, &package_name, manifest.as_ref())?;
+    let has_library = targets.iter().any(|target| target.kind == TargetKind::Lib);
+    let targets = targets
+        .into_iter()
+        .filter(|target| kinds.contains(&target.kind))
+        .collect::<Vec<_>>();
+    if targets.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("no crate root to draw in {}", directory.display()),
+        ));
+    }
+    let mut visited = Set::new();
+    if configuration.targets.is_none() {
+        let roots = targets
+            .iter()
+            .map(|target| (target.root.clone(), target.key_in_sources(directory)))
+            .collect::<Vec<_>>();
+        package.binaries = roots
+            .iter()
+            .map(|(_, key)| key.clone())
+            .filter(|key| key.len() > 1)
+            .collect();
+        let trie = read_crate(&roots, &mut package, &cfgs, configuration, &mut visited)?;
+        for directory in crate_targets::covered_directories(directory, kinds) {
+            files_reader::report_stray_files(&directory, &visited);
+        }
+        return Ok((trie, package));
+    }
+    let mut crates = Vec::new();
+    for target in &targets {
+        let mut crate_package = package.clone();
+        if target.kind.takes_dev_dependencies() {
+            crate_package.extern_crates = dev_crates.clone();
+        }
+        if target.kind != TargetKind::Lib {
+            crate_package.name = String::new();
+            // the library is named by the other targets, even when it is not drawn
+            if let Some(crates) = crate_package.extern_crates.as_mut().filter(|_| has_library) {
+                crates.insert(package.name.clone());
+            }
+        }
+        let roots = [(target.root.clone(), vec![target.root_name().to_string()])];
+        // a file shared by several targets, such as `tests/common/mod.rs`, is a module of each one
+        let mut crate_visited = Set::new();
+        let trie = read_crate(
+            &roots,
+            &mut crate_package,
+            &cfgs,
+            configuration,
+            &mut crate_visited,
+        )?;
+        visited.extend(crate_visited);
+        crates.push((target.key(), trie, crate_package));
+    }
+    for directory in crate_targets::covered_directories(directory, kinds) {
+        files_reader::report_stray_files(&directory, &visited);
+    }
+    if crates.len() == 1 {
+        let (_, trie, crate_package) = crates.remove(0);
+        return Ok((
+            trie,
+            Package {
+                name: package.name,
+                ..crate_package
+            },
+        ));
+    }
     let mut trie = DependenciesGraph::new();
-    files_reader::build_dependencies_trie::<RustParser>(path, &mut trie, skip_length).expect(
-        "Unable to read ./src; please consider changing to the root directory of your package.",
+    for (key, crate_trie, crate_package) in crates {
+        trie.children.insert(key.clone(), crate_trie);
+        package.members.insert(key, crate_package);
+    }
+    Ok((trie, package))
+}
+
+/**
+ * Draws the graph, after reporting once each import which is left out for naming no known crate.
+ */
+fn show(trie: &DependenciesGraph, package: &Package) -> String {
+    for (import, module) in RustDependencyProcessor::unresolved_imports(trie, package) {
+        eprintln!(
+            "Warning: unresolved import {} in {}; it has been ignored.",
+            import, module
+        );
+    }
+    DotFormatter::show::<RustDependencyProcessor>(trie, package)
+}
+
+/**
+ * Draws the package whose sources are in `directory`, the `src` directory of the package.
+ */
+pub fn run_app(directory: &str, pkg_name: &str, configuration: &Configuration) -> String {
+    let sources = Path::new(directory);
+    let directory = sources.parent().unwrap_or(sources);
+    let metadata = read_metadata(directory, configuration);
+    let (trie, package) = read_package(directory, pkg_name, configuration, None, metadata.as_ref())
+        .unwrap_or_else(|error| {
+            panic!(
+                "Unable to read the package ({}); please consider changing to the root directory of your package.",
+                error
+            )
+        });
+    show(&trie, &package)
+}
+
+/**
+ * Draws the members of the workspace rooted at `directory`, each in the cluster of its crate name.
+ * The members whose sources cannot be read are reported and left out.
+ */
+pub fn run_workspace(directory: &str, configuration: &Configuration) -> String {
+    let root = Path::new(directory);
+    let manifest = Manifest::read(&root.join(MANIFEST)).expect(
+        "Unable to read ./Cargo.toml; please consider changing to the root directory of your workspace.",
     );
-    DotFormatter::show::<RustDependencyProcessor>(&trie, pkg_name)
+    let metadata = read_metadata(root, configuration);
+    let mut trie = DependenciesGraph::new();
+    let mut members = Map::new();
+    for member in manifest.workspace_members(root) {
+        let name = member
+            .file_name()
+            .map(|name| name.to_string_lossy().replace('-', "_"))
+            .unwrap_or_default();
+        match read_package(
+            &member,
+            &name,
+            configuration,
+            Some(&manifest),
+            metadata.as_ref(),
+        ) {
+            Ok((member_trie, package)) => {
+                trie.children.insert(package.name.clone(), member_trie);
+                members.insert(package.name.clone(), package);
+            }
+            Err(error) => eprintln!(
+                "Warning: unable to read the member {} ({}); it has been ignored.",
+                member.display(),
+                error
+            ),
+        }
+    }
+    let workspace = Package {
+        members,
+        ..Package::default()
+    };
+    show(&trie, &workspace)
 }
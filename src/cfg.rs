@@ -0,0 +1,372 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
+
+use crate::targets::target_cfgs;
+
+const ALL: &str = "all";
+const ANY: &str = "any";
+const NOT: &str = "not";
+const FEATURE: &str = "feature";
+const TEST: &str = "test";
+const TARGET_FAMILY: &str = "target_family";
+/** The names set by rustc according to the target family. */
+const FAMILY_NAMES: [&str; 2] = ["unix", "windows"];
+
+/**
+ * A `cfg` predicate, such as `all(unix, feature = "json")`.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Predicate {
+    Option(String),
+    KeyValue(String, String),
+    All(Vec<Predicate>),
+    Any(Vec<Predicate>),
+    Not(Box<Predicate>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Identifier(String),
+    Literal(String),
+    Punctuation(char),
+}
+
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | ')' | ',' | '=' => tokens.push(Token::Punctuation(c)),
+            '"' => {
+                let mut literal = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => literal.push(chars.next()?),
+                        c => literal.push(c),
+                    }
+                }
+                tokens.push(Token::Literal(literal));
+            }
+            _ if c.is_alphanumeric() || c == '_' => {
+                let mut identifier = String::from(c);
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '#')
+                {
+                    identifier.push(c);
+                }
+                tokens.push(Token::Identifier(identifier));
+            }
+            _ if c.is_whitespace() => {}
+            _ => return None,
+        }
+    }
+    Some(tokens)
+}
+
+struct PredicateParser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl PredicateParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn next_if(&mut self, expected: &Token) -> bool {
+        let found = self.tokens.get(self.index) == Some(expected);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    /**
+     * The comma-separated predicates between parentheses.
+     */
+    fn list(&mut self) -> Option<Vec<Predicate>> {
+        let mut predicates = vec![];
+        while !self.next_if(&Token::Punctuation(')')) {
+            predicates.push(self.predicate()?);
+            if !self.next_if(&Token::Punctuation(',')) {
+                self.next_if(&Token::Punctuation(')')).then_some(())?;
+                break;
+            }
+        }
+        Some(predicates)
+    }
+
+    fn predicate(&mut self) -> Option<Predicate> {
+        let Token::Identifier(name) = self.next()? else {
+            return None;
+        };
+        if self.next_if(&Token::Punctuation('=')) {
+            let Token::Literal(value) = self.next()? else {
+                return None;
+            };
+            return Some(Predicate::KeyValue(name, value));
+        }
+        if !self.next_if(&Token::Punctuation('(')) {
+            return Some(Predicate::Option(name));
+        }
+        let mut predicates = self.list()?;
+        match name.as_str() {
+            ALL => Some(Predicate::All(predicates)),
+            ANY => Some(Predicate::Any(predicates)),
+            NOT if predicates.len() == 1 => Some(Predicate::Not(Box::new(predicates.pop()?))),
+            _ => None,
+        }
+    }
+}
+
+impl Predicate {
+    /**
+     * Parses the contents of a `#[cfg(...)]` attribute.
+     */
+    pub fn parse(text: &str) -> Option<Predicate> {
+        let mut parser = PredicateParser {
+            tokens: tokenize(text)?,
+            index: 0,
+        };
+        let predicate = parser.predicate()?;
+        (parser.index == parser.tokens.len()).then_some(predicate)
+    }
+}
+
+/**
+ * The cfgs of the platform the crate is built for: names such as `unix`, and keys such as `target_os`.
+ * Only the names and keys which have been given are decided.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Platform {
+    names: Set<String>,
+    values: Map<String, Set<String>>,
+    decided: Set<String>,
+}
+
+impl Platform {
+    pub fn of_target(triple: &str) -> Option<Self> {
+        let mut platform = Platform::default();
+        for (key, value) in target_cfgs(triple)? {
+            platform.insert_value(key, value);
+        }
+        // bare-metal targets have no family, hence neither `unix` nor `windows`
+        platform.decided.insert(TARGET_FAMILY.to_string());
+        platform
+            .decided
+            .extend(FAMILY_NAMES.iter().map(|name| name.to_string()));
+        Some(platform)
+    }
+
+    fn insert_value(&mut self, key: &str, value: &str) {
+        if key == TARGET_FAMILY {
+            self.decided
+                .extend(FAMILY_NAMES.iter().map(|name| name.to_string()));
+            if FAMILY_NAMES.contains(&value) {
+                self.names.insert(value.to_string());
+            }
+        }
+        self.decided.insert(key.to_string());
+        self.values
+            .entry(key.to_string())
+            .or_default()
+            .insert(value.to_string());
+    }
+
+    /**
+     * Adds a cfg given as `name` or `key="value"`.
+     */
+    pub fn insert(&mut self, cfg: &str) -> Result<(), String> {
+        match Predicate::parse(cfg) {
+            Some(Predicate::Option(name)) => {
+                self.decided.insert(name.clone());
+                self.names.insert(name);
+            }
+            Some(Predicate::KeyValue(key, value)) => self.insert_value(&key, &value),
+            _ => {
+                return Err(format!(
+                    "Invalid cfg: {} (expected name or key=\"value\").",
+                    cfg
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self, predicate: &Predicate) -> Option<bool> {
+        match predicate {
+            Predicate::Option(name) => self
+                .decided
+                .contains(name)
+                .then(|| self.names.contains(name)),
+            Predicate::KeyValue(key, value) => self.decided.contains(key).then(|| {
+                self.values
+                    .get(key)
+                    .is_some_and(|values| values.contains(value))
+            }),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * What is known of the configuration the crate is built with.
+ * A predicate whose value cannot be told from it is assumed to hold,
+ * so that the graph shows the union of the possible configurations.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CfgSet {
+    /** The enabled Cargo features, if they are known. */
+    pub features: Option<Set<String>>,
+    pub platform: Platform,
+}
+
+impl CfgSet {
+    /**
+     * `all` is decided by any false predicate, and `any` by any true one.
+     */
+    fn combine(
+        &self,
+        predicates: &[Predicate],
+        decisive: bool,
+        tests: Option<bool>,
+    ) -> Option<bool> {
+        let values = predicates
+            .iter()
+            .map(|predicate| self.evaluate_with(predicate, tests))
+            .collect::<Vec<_>>();
+        if values.contains(&Some(decisive)) {
+            Some(decisive)
+        } else if values.contains(&None) {
+            None
+        } else {
+            Some(!decisive)
+        }
+    }
+
+    /**
+     * `tests` tells whether the crate is built for its tests, if that is to be decided.
+     */
+    fn evaluate_with(&self, predicate: &Predicate, tests: Option<bool>) -> Option<bool> {
+        match predicate {
+            Predicate::Option(name) if name == TEST => tests,
+            Predicate::KeyValue(key, value) if key == FEATURE => self
+                .features
+                .as_ref()
+                .map(|features| features.contains(value)),
+            Predicate::Option(_) | Predicate::KeyValue(_, _) => self.platform.evaluate(predicate),
+            Predicate::All(predicates) => self.combine(predicates, false, tests),
+            Predicate::Any(predicates) => self.combine(predicates, true, tests),
+            Predicate::Not(predicate) => self.evaluate_with(predicate, tests).map(|value| !value),
+        }
+    }
+
+    pub fn evaluate(&self, predicate: &Predicate) -> Option<bool> {
+        self.evaluate_with(predicate, None)
+    }
+
+    /**
+     * Whether the items annotated with `#[cfg(...)]`, given its contents, are known to be compiled out.
+     * Unparsable predicates are assumed to hold.
+     */
+    pub fn disables(&self, predicate: &str) -> bool {
+        Predicate::parse(predicate)
+            .is_some_and(|predicate| self.evaluate(&predicate) == Some(false))
+    }
+
+    /**
+     * Whether the items annotated with `#[cfg(...)]`, given its contents, are only compiled for the tests,
+     * as with `test` or `all(test, feature = "x")`, or `any(test, windows)` when building for unix.
+     */
+    pub fn is_test_only(&self, predicate: &str) -> bool {
+        Predicate::parse(predicate).is_some_and(|predicate| {
+            self.evaluate_with(&predicate, Some(false)) == Some(false)
+                && self.evaluate_with(&predicate, Some(true)) != Some(false)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet as Set;
+
+    use super::{CfgSet, Platform, Predicate};
+
+    #[test]
+    fn it_parses_predicates() {
+        assert_eq!(
+            Predicate::parse(r#"all(unix, not(feature = "json"), any(),)"#),
+            Some(Predicate::All(vec![
+                Predicate::Option(String::from("unix")),
+                Predicate::Not(Box::new(Predicate::KeyValue(
+                    String::from("feature"),
+                    String::from("json")
+                ))),
+                Predicate::Any(vec![]),
+            ]))
+        );
+        assert_eq!(Predicate::parse("not(unix, windows)"), None);
+        assert_eq!(Predicate::parse("unix windows"), None);
+    }
+
+    #[test]
+    fn it_evaluates_features() {
+        let cfgs = CfgSet {
+            features: Some(Set::from([String::from("json")])),
+            ..CfgSet::default()
+        };
+        assert!(!cfgs.disables(r#"feature = "json""#));
+        assert!(cfgs.disables(r#"feature = "yaml""#));
+        assert!(cfgs.disables(r#"all(unix, feature = "yaml")"#));
+        assert!(!cfgs.disables(r#"all(unix, feature = "json")"#));
+        assert!(!cfgs.disables(r#"any(unix, feature = "yaml")"#));
+        assert!(cfgs.disables(r#"not(any(feature = "yaml", feature = "json"))"#));
+        assert!(!CfgSet::default().disables(r#"feature = "yaml""#));
+    }
+
+    #[test]
+    fn it_evaluates_platforms() {
+        let cfgs = CfgSet {
+            platform: Platform::of_target("x86_64-pc-windows-msvc").unwrap(),
+            ..CfgSet::default()
+        };
+        assert!(cfgs.disables("unix"));
+        assert!(!cfgs.disables("windows"));
+        assert!(cfgs.disables(r#"target_os = "linux""#));
+        assert!(!cfgs.disables(r#"all(target_arch = "x86_64", target_env = "msvc")"#));
+        assert!(!cfgs.disables(r#"any(target_feature = "avx2", docsrs)"#));
+        let mut platform = Platform::default();
+        platform.insert(r#"target_os="linux""#).unwrap();
+        platform.insert("my_cfg").unwrap();
+        let cfgs = CfgSet {
+            platform,
+            ..CfgSet::default()
+        };
+        assert!(cfgs.disables(r#"target_os = "macos""#));
+        assert!(cfgs.disables("not(my_cfg)"));
+        assert!(!cfgs.disables("unix"));
+        assert!(Platform::default().insert("all(unix)").is_err());
+    }
+
+    #[test]
+    fn it_tells_the_test_only_predicates() {
+        let cfgs = CfgSet {
+            platform: Platform::of_target("x86_64-unknown-linux-gnu").unwrap(),
+            ..CfgSet::default()
+        };
+        assert!(cfgs.is_test_only("test"));
+        assert!(cfgs.is_test_only(r#"all(test, feature = "json")"#));
+        assert!(cfgs.is_test_only("any(test, windows)"));
+        assert!(!cfgs.is_test_only("any(test, unix)"));
+        assert!(!cfgs.is_test_only("any(test, docsrs)"));
+        assert!(!cfgs.is_test_only("not(test)"));
+        assert!(!cfgs.is_test_only("unix"));
+        assert!(!cfgs.disables(r#"all(test, feature = "json")"#));
+    }
+}
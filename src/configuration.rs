@@ -0,0 +1,227 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+use std::{collections::BTreeSet as Set, fmt, path::PathBuf, str::FromStr};
+
+use crate::{
+    cfg::Platform,
+    targets::{known_targets, target_cfgs},
+};
+
+/**
+ * The parser reading the dependencies of each file, as selected by `--parser`.
+ * Only `syn` tells apart the paths named by the signatures of items, drawn as `signature` edges:
+ * `regex` draws the same names as plain `use` edges.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ParserKind {
+    #[default]
+    Regex,
+    Syn,
+}
+
+impl FromStr for ParserKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "regex" => Ok(Self::Regex),
+            "syn" => Ok(Self::Syn),
+            _ => Err(format!("Unknown parser: {} (expected regex or syn).", s)),
+        }
+    }
+}
+
+/**
+ * Whether the vertices of the graph are modules, or the top-level items nested inside their modules.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Granularity {
+    #[default]
+    Module,
+    Item,
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "module" => Ok(Self::Module),
+            "item" => Ok(Self::Item),
+            _ => Err(format!(
+                "Unknown granularity: {} (expected module or item).",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * The kind of a Cargo target, that is of a crate of the package.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TargetKind {
+    Lib,
+    Bin,
+    Example,
+    Test,
+    Bench,
+}
+
+impl FromStr for TargetKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lib" => Ok(Self::Lib),
+            "bin" => Ok(Self::Bin),
+            "example" => Ok(Self::Example),
+            "test" => Ok(Self::Test),
+            "bench" => Ok(Self::Bench),
+            _ => Err(format!(
+                "Unknown target kind: {} (expected lib, bin, example, test or bench).",
+                s
+            )),
+        }
+    }
+}
+
+impl TargetKind {
+    /**
+     * The tests, examples and benches are built along with the dev-dependencies of the package.
+     */
+    pub fn takes_dev_dependencies(self) -> bool {
+        matches!(self, Self::Example | Self::Test | Self::Bench)
+    }
+}
+
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Lib => "lib",
+            Self::Bin => "bin",
+            Self::Example => "example",
+            Self::Test => "test",
+            Self::Bench => "bench",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/**
+ * The kinds of Cargo targets to draw, as selected by `--targets`: the library and the binaries by default.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct TargetSelection(pub Set<TargetKind>);
+
+impl Default for TargetSelection {
+    fn default() -> Self {
+        TargetSelection(Set::from([TargetKind::Lib, TargetKind::Bin]))
+    }
+}
+
+impl FromStr for TargetSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .filter(|kind| !kind.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(TargetSelection)
+    }
+}
+
+/**
+ * The Cargo features to build with, as selected by `--features`, `--all-features` and `--no-default-features`.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeatureSelection {
+    pub features: Vec<String>,
+    pub all_features: bool,
+    pub no_default_features: bool,
+}
+
+/**
+ * The platform to build for, as selected by `--target` and `--cfg`.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlatformSelection {
+    target: Option<String>,
+    cfgs: Vec<String>,
+}
+
+impl PlatformSelection {
+    pub fn set_target(&mut self, triple: &str) -> Result<(), String> {
+        if target_cfgs(triple).is_none() {
+            return Err(format!(
+                "Unknown target: {} (expected one of {}).",
+                triple,
+                known_targets().join(", ")
+            ));
+        }
+        self.target = Some(triple.to_string());
+        Ok(())
+    }
+
+    pub fn add_cfg(&mut self, cfg: &str) -> Result<(), String> {
+        Platform::default().insert(cfg)?;
+        self.cfgs.push(cfg.to_string());
+        Ok(())
+    }
+
+    /**
+     * The cfgs of the target, if any, along with the given ones.
+     */
+    pub(crate) fn platform(&self) -> Platform {
+        let mut platform = self
+            .target
+            .as_deref()
+            .and_then(Platform::of_target)
+            .unwrap_or_default();
+        for cfg in &self.cfgs {
+            // already checked by add_cfg
+            let _ = platform.insert(cfg);
+        }
+        platform
+    }
+}
+
+/**
+ * Where the dependencies of the packages are read from, as selected by `--metadata`.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataSource {
+    /** `cargo metadata --offline`, run in the directory of the package or of the workspace. */
+    Cargo,
+    /** The output of `cargo metadata` saved to a file. */
+    File(PathBuf),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Configuration {
+    pub parser: ParserKind,
+    pub granularity: Granularity,
+    /** Whether to show the dependencies of the items annotated with `#[cfg(test)]`. */
+    pub tests: bool,
+    /** Whether to resolve the dependencies on facade modules through their `pub use` re-exports. */
+    pub follow_reexports: bool,
+    /** Whether to list the places in the sources which each arrow comes from. */
+    pub spans: bool,
+    /** Whether to draw all the members of a workspace, each in the cluster of its crate. */
+    pub workspace: bool,
+    /**
+     * The kinds of Cargo targets to draw, each in its own cluster when there are several, if selected.
+     * Otherwise the library and the binaries are drawn together as a single crate.
+     */
+    pub targets: Option<TargetSelection>,
+    /** The items gated by `#[cfg(feature = "...")]` are dropped if not selected. All of them are kept by default. */
+    pub features: Option<FeatureSelection>,
+    /** The items gated by platform `#[cfg(...)]` predicates are dropped if they do not hold for the selected platform. */
+    pub platform: PlatformSelection,
+    /** The dependencies read from `cargo metadata`, if selected, tell the external crates from the unresolved imports. */
+    pub metadata: Option<MetadataSource>,
+}
@@ -0,0 +1,346 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+use std::{
+    collections::BTreeSet as Set,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    configuration::TargetKind,
+    files_reader::is_rust_file,
+    manifest::{Manifest, TargetEntry},
+};
+
+const EXTENSION: &str = "rs";
+const SRC: &str = "src";
+const LIB: &str = "lib";
+const MAIN: &str = "main";
+const BIN: &str = "bin";
+const EXAMPLES: &str = "examples";
+const TESTS: &str = "tests";
+const BENCHES: &str = "benches";
+
+/**
+ * A crate of the package, as Cargo builds it.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct CrateTarget {
+    pub kind: TargetKind,
+    /** The crate name of the target. */
+    pub name: String,
+    pub root: PathBuf,
+}
+
+impl CrateTarget {
+    /**
+     * The key of the target among the others, which tells apart a library and a binary of the same name.
+     */
+    pub fn key(&self) -> String {
+        format!("{}_{}", self.kind, self.name)
+    }
+
+    /**
+     * The key of the root module: `lib` for the library and `main` for the other targets,
+     * as the paths relative to the crate root expect.
+     */
+    pub fn root_name(&self) -> &'static str {
+        match self.kind {
+            TargetKind::Lib => LIB,
+            _ => MAIN,
+        }
+    }
+
+    /**
+     * The key of the root module when the library and the binaries of the package in `directory`
+     * are drawn as a single crate: its path in `src`, as `bin/foo` for `src/bin/foo.rs`.
+     * The library is always keyed `lib`, and a binary out of `src` is keyed as if it were in `src/bin`.
+     */
+    pub fn key_in_sources(&self, directory: &Path) -> Vec<String> {
+        let relative_path = self.root.with_extension("");
+        match relative_path.strip_prefix(directory.join(SRC)) {
+            _ if self.kind == TargetKind::Lib => vec![LIB.to_string()],
+            Ok(path) => path
+                .iter()
+                .map(|component| component.to_string_lossy().into())
+                .collect(),
+            Err(_) => vec![BIN.to_string(), self.name.clone()],
+        }
+    }
+}
+
+/**
+ * The directory in which Cargo looks for the targets of a kind, other than the library and the main binary.
+ */
+fn kind_directory(directory: &Path, kind: TargetKind) -> PathBuf {
+    match kind {
+        TargetKind::Lib => directory.join(SRC),
+        TargetKind::Bin => directory.join(SRC).join(BIN),
+        TargetKind::Example => directory.join(EXAMPLES),
+        TargetKind::Test => directory.join(TESTS),
+        TargetKind::Bench => directory.join(BENCHES),
+    }
+}
+
+/**
+ * `foo.rs` and `foo/main.rs` in the directory of their kind, as Cargo discovers the binaries, examples, tests and benches.
+ */
+fn discover(directory: &Path, kind: TargetKind) -> Vec<CrateTarget> {
+    let Ok(entries) = kind_directory(directory, kind).read_dir() else {
+        return vec![];
+    };
+    let mut targets = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            let root = if path.is_dir() {
+                path.join(MAIN).with_extension(EXTENSION)
+            } else {
+                path.clone()
+            };
+            let name = path.file_stem()?.to_string_lossy().replace('-', "_");
+            is_rust_file(&root).then_some(CrateTarget { kind, name, root })
+        })
+        .collect::<Vec<_>>();
+    targets.sort_by(|a, b| a.root.cmp(&b.root));
+    targets
+}
+
+/**
+ * Where Cargo expects the root of a target listed without `path`.
+ */
+fn inferred_roots(
+    directory: &Path,
+    kind: TargetKind,
+    name: &str,
+    package_name: &str,
+) -> Vec<PathBuf> {
+    let src = directory.join(SRC);
+    let kind_directory = kind_directory(directory, kind);
+    match kind {
+        TargetKind::Lib => vec![src.join(LIB).with_extension(EXTENSION)],
+        TargetKind::Bin if name == package_name => vec![
+            src.join(MAIN).with_extension(EXTENSION),
+            kind_directory.join(name).with_extension(EXTENSION),
+            kind_directory
+                .join(name)
+                .join(MAIN)
+                .with_extension(EXTENSION),
+        ],
+        _ => vec![
+            kind_directory.join(name).with_extension(EXTENSION),
+            kind_directory
+                .join(name)
+                .join(MAIN)
+                .with_extension(EXTENSION),
+        ],
+    }
+}
+
+/**
+ * A target listed in the manifest, whose root must exist.
+ * The library is named `library_name`, which already takes its `name` setting into account.
+ */
+fn declared_target(
+    directory: &Path,
+    entry: &TargetEntry,
+    library_name: &str,
+    package_name: &str,
+) -> Result<CrateTarget> {
+    let name = match (entry.kind, &entry.name) {
+        (TargetKind::Lib, _) => library_name.to_string(),
+        (_, Some(name)) => name.replace('-', "_"),
+        (_, None) => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{} has no name", entry),
+            ))
+        }
+    };
+    let roots = match &entry.path {
+        Some(path) => vec![directory.join(path)],
+        None => inferred_roots(directory, entry.kind, &name, package_name),
+    };
+    match roots.iter().find(|root| is_rust_file(root)) {
+        Some(root) => Ok(CrateTarget {
+            kind: entry.kind,
+            name,
+            root: root.clone(),
+        }),
+        None => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("{}: unable to find {}", entry, roots[0].display()),
+        )),
+    }
+}
+
+/**
+ * The targets of the package in `directory`: those listed in the manifest,
+ * along with those Cargo discovers in the usual places unless told otherwise.
+ * The library is named `library_name` and the main binary `package_name`.
+ */
+pub fn find_targets(
+    directory: &Path,
+    library_name: &str,
+    package_name: &str,
+    manifest: Option<&Manifest>,
+) -> Result<Vec<CrateTarget>> {
+    let discovers = |kind| match manifest {
+        Some(manifest) => manifest.discovers(kind),
+        None => true,
+    };
+    let src = directory.join(SRC);
+    let root = |kind, name: &str, file: &str| {
+        Some(src.join(file).with_extension(EXTENSION))
+            .filter(|root| is_rust_file(root) && discovers(kind))
+            .map(|root| CrateTarget {
+                kind,
+                name: name.to_string(),
+                root,
+            })
+    };
+    let mut targets = Vec::new();
+    targets.extend(root(TargetKind::Lib, library_name, LIB));
+    targets.extend(root(TargetKind::Bin, package_name, MAIN));
+    for kind in [
+        TargetKind::Bin,
+        TargetKind::Example,
+        TargetKind::Test,
+        TargetKind::Bench,
+    ] {
+        if discovers(kind) {
+            targets.append(&mut discover(directory, kind));
+        }
+    }
+    let entries = manifest.map(Manifest::declared_targets).unwrap_or_default();
+    for entry in &entries {
+        let target = declared_target(directory, entry, library_name, package_name)?;
+        // a listed target takes the place of the one found under its name or at its root
+        targets.retain(|other| {
+            !(other.kind == target.kind && other.name == target.name) && other.root != target.root
+        });
+        targets.push(target);
+    }
+    targets.sort_by_key(|target| target.kind);
+    Ok(targets)
+}
+
+/**
+ * The directories of the package all of whose targets are of the given kinds:
+ * the files they hold that no target reads are stray files.
+ */
+pub fn covered_directories(directory: &Path, kinds: &Set<TargetKind>) -> Vec<PathBuf> {
+    [
+        (SRC, &[TargetKind::Lib, TargetKind::Bin][..]),
+        (EXAMPLES, &[TargetKind::Example]),
+        (TESTS, &[TargetKind::Test]),
+        (BENCHES, &[TargetKind::Bench]),
+    ]
+    .into_iter()
+    .filter(|(_, held)| held.iter().all(|kind| kinds.contains(kind)))
+    .map(|(name, _)| directory.join(name))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::{configuration::TargetKind, crate_targets::find_targets, manifest::Manifest};
+
+    #[test]
+    fn it_finds_the_targets_of_the_package() {
+        let targets = find_targets(Path::new("tests/web_app"), "web_app", "web_app", None)
+            .unwrap()
+            .into_iter()
+            .map(|target| (target.key(), target.root))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            vec![
+                (
+                    String::from("lib_web_app"),
+                    PathBuf::from("tests/web_app/src/lib.rs")
+                ),
+                (
+                    String::from("bin_web_app"),
+                    PathBuf::from("tests/web_app/src/main.rs")
+                ),
+                (
+                    String::from("example_seed"),
+                    PathBuf::from("tests/web_app/examples/seed.rs")
+                ),
+                (
+                    String::from("test_api"),
+                    PathBuf::from("tests/web_app/tests/api/main.rs")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_keys_the_roots_by_their_path_in_the_sources() {
+        let manifest = Manifest::read(Path::new("tests/custom_targets/Cargo.toml"));
+        let keys = |directory: &str, manifest: Option<&Manifest>| {
+            let directory = Path::new(directory);
+            find_targets(directory, "my_app", "my_app", manifest)
+                .unwrap()
+                .into_iter()
+                .filter(|target| target.kind <= TargetKind::Bin)
+                .map(|target| target.key_in_sources(directory).join("/"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys("tests/web_app", None), vec!["lib", "main"]);
+        assert_eq!(
+            keys("tests/custom_targets", manifest.as_ref()),
+            vec!["lib", "bin/tool"]
+        );
+    }
+
+    #[test]
+    fn it_finds_the_targets_listed_in_the_manifest() {
+        let manifest = Manifest::read(Path::new("tests/custom_targets/Cargo.toml"));
+        let targets = find_targets(
+            Path::new("tests/custom_targets"),
+            "custom_targets",
+            "custom_targets",
+            manifest.as_ref(),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|target| (target.key(), target.root))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            vec![
+                (
+                    String::from("lib_custom_targets"),
+                    PathBuf::from("tests/custom_targets/lib/core.rs")
+                ),
+                (
+                    String::from("bin_tool"),
+                    PathBuf::from("tests/custom_targets/tools/tool.rs")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_names_the_manifest_entry_whose_root_is_missing() {
+        let manifest = Manifest::read(Path::new("tests/custom_targets/Cargo.toml"));
+        let error = find_targets(
+            Path::new("tests/web_app"),
+            "custom_targets",
+            "custom_targets",
+            manifest.as_ref(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"[lib] path = "lib/core.rs": unable to find tests/web_app/lib/core.rs"#
+        );
+    }
+}
@@ -4,8 +4,89 @@
  * SPDX-License-Identifier: GPL-3.0-only
  */
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct FilePath(pub Vec<String>);
+use std::{fmt, path::PathBuf};
+
+const GLOB: &str = "*";
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FilePath(pub Vec<String>);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct DependencyPath(pub Vec<String>);
+
+/**
+ * How a module depends on another one.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EdgeKind {
+    /** An item imported by `use` or named by its path. */
+    #[default]
+    Use,
+    /** A type or trait named by the signature of an item: fields, parameters, return types, bounds. */
+    Signature,
+    /** A macro invocation. */
+    Macro,
+    /** The trait of an `impl ... for` block. */
+    Implements,
+    /** A derive macro, as in `#[derive(crate::macros::Builder)]`. */
+    Derives,
+    /** An attribute macro, or a path given to an attribute, as in `#[serde(with = "crate::time_fmt")]`. */
+    Attribute,
+}
+
+/**
+ * Where a dependency comes from: the `use` keyword of its declaration, or the start of its path.
+ * Lines and columns start at 1. The parsers only see the contents of a file, so they leave the file empty.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Span {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Dependency {
+    pub path: DependencyPath,
+    pub kind: EdgeKind,
+    pub test_only: bool,
+    /**
+     * Imports all the items of a module (`use foo::*`), whose path stops at the module.
+     */
+    pub glob: bool,
+    /**
+     * Imported by `pub use`, so that the importers of this module can see the items too.
+     */
+    pub reexported: bool,
+    /**
+     * The path of a `use` declaration, or a path starting with `::`,
+     * which the 2015 edition resolves from the crate root.
+     */
+    pub rooted: bool,
+    /**
+     * The path starts with `::`: from the 2018 edition on, it names an external crate,
+     * even when a module of the same name is in scope.
+     */
+    pub global: bool,
+    pub span: Option<Span>,
+}
+
+impl From<DependencyPath> for Dependency {
+    fn from(DependencyPath(mut path): DependencyPath) -> Self {
+        let glob = path.last().is_some_and(|last| last == GLOB);
+        if glob {
+            path.pop();
+        }
+        Self {
+            path: DependencyPath(path),
+            glob,
+            ..Self::default()
+        }
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{dependencies::DependencyPath, trie::Trie};
+use std::{collections::BTreeMap as Map, path::PathBuf};
+
+use crate::{
+    dependencies::{Dependency, DependencyPath},
+    trie::Trie,
+};
 
 /**
  * Copyright 2023 Thomas Hügel.
@@ -6,7 +11,94 @@ use crate::{dependencies::DependencyPath, trie::Trie};
  * SPDX-License-Identifier: GPL-3.0-only
  */
 
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Module {
+    pub dependencies: Vec<Dependency>,
+    /**
+     * The file of a module loaded through a `#[path]` attribute,
+     * as it cannot be deduced from the path of the module.
+     */
+    pub file: Option<PathBuf>,
+    /** The dependencies of each top-level item, when the graph shows items. */
+    pub items: Map<String, Vec<Dependency>>,
+}
+
 /**
  * At each node, the value represents the full path from the root, and the dependencies read at this node.
  */
-pub type DependenciesGraph = Trie<String, Vec<DependencyPath>>;
+pub type DependenciesGraph = Trie<String, Module>;
+
+/**
+ * The items re-exported by each module (`pub use self::bar::Widget;`), by name,
+ * with the path they are imported from.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Reexports(Map<Vec<String>, Map<String, DependencyPath>>);
+
+impl Reexports {
+    pub fn new(trie: &DependenciesGraph) -> Self {
+        let mut reexports = Map::new();
+        trie.for_each_entry(&mut |key, module| {
+            let items = module
+                .dependencies
+                .iter()
+                .filter(|dependency| dependency.reexported && !dependency.glob)
+                .filter_map(|dependency| {
+                    let name = dependency.path.0.last()?;
+                    Some((name.clone(), dependency.path.clone()))
+                })
+                .collect::<Map<_, _>>();
+            if !items.is_empty() {
+                reexports.insert(key.to_vec(), items);
+            }
+        });
+        Reexports(reexports)
+    }
+
+    pub fn get(&self, module: &[String], item: &str) -> Option<&DependencyPath> {
+        self.0.get(module).and_then(|items| items.get(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap as Map, VecDeque};
+
+    use crate::{
+        dependencies::{Dependency, DependencyPath},
+        dependencies_graph::{DependenciesGraph, Module, Reexports},
+    };
+
+    #[test]
+    fn it_indexes_reexports() {
+        let mut trie = DependenciesGraph::new();
+        let widget = DependencyPath(vec![
+            String::from("self"),
+            String::from("bar"),
+            String::from("Widget"),
+        ]);
+        trie.insert(
+            VecDeque::from([String::from("foo"), String::from("mod")]),
+            Module {
+                dependencies: vec![
+                    Dependency {
+                        reexported: true,
+                        ..widget.clone().into()
+                    },
+                    Dependency {
+                        reexported: true,
+                        ..DependencyPath(vec![String::from("self"), String::from("*")]).into()
+                    },
+                    DependencyPath(vec![String::from("std"), String::from("fmt")]).into(),
+                ],
+                file: None,
+                items: Map::new(),
+            },
+        );
+        let reexports = Reexports::new(&trie);
+        let facade = [String::from("foo"), String::from("mod")];
+        assert_eq!(reexports.get(&facade, "Widget"), Some(&widget));
+        assert_eq!(reexports.get(&facade, "fmt"), None);
+        assert_eq!(reexports.get(&facade, "self"), None);
+    }
+}
@@ -1,15 +1,46 @@
 use crate::{
-    dependencies::{DependencyPath, FilePath},
+    dependencies::{Dependency, FilePath},
     dependencies_graph::DependenciesGraph,
+    package::Package,
 };
 
 pub mod rust_processor;
 
+/**
+ * The module a dependency leads to, and how it was reached.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Target {
+    pub path: FilePath,
+    /**
+     * The local name of an external crate, if it differs from the crate name of its package.
+     */
+    pub alias: Option<String>,
+    /**
+     * The item of the module, when the graph shows items and the module defines it.
+     */
+    pub item: Option<String>,
+    /**
+     * The facade modules whose re-exports led to the target, in order.
+     */
+    pub via: Vec<FilePath>,
+}
+
 pub trait DependencyProcessor {
     fn compute_target(
         trie: &DependenciesGraph,
         file_path: &FilePath,
-        dependency: &DependencyPath,
-        pkg_name: &str,
-    ) -> FilePath;
+        dependency: &Dependency,
+        package: &Package,
+    ) -> Target;
+
+    /**
+     * Like `compute_target`, except that a glob import may lead to several modules.
+     */
+    fn compute_targets(
+        trie: &DependenciesGraph,
+        file_path: &FilePath,
+        dependency: &Dependency,
+        package: &Package,
+    ) -> Vec<Target>;
 }
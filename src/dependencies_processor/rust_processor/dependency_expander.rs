@@ -7,26 +7,45 @@ use super::{Dependency, DependencyKind, MOD};
 const CRATE: &str = "crate";
 const SELF: &str = "self";
 const SUPER: &str = "super";
+const CRATE_ROOTS: [&str; 2] = ["lib", "main"];
 
+/**
+ * A path `from_root` starts at the crate root rather than at the source module,
+ * unless it starts with `self` or `super`.
+ * The modules of a binary drawn along with the library are keyed under the directory of its root,
+ * `binary_root`, from which `crate` is resolved; the package name still leads to the library.
+ */
 pub fn expand_dependency(
     dependency_components: &[String],
     pkg_name: &str,
     mut source_file_path: Vec<String>,
+    binary_root: Option<&[String]>,
+    from_root: bool,
 ) -> Dependency {
     let fst = dependency_components
         .first()
         .expect("A dependency should not be empty!");
+    let crate_key = binary_root.map_or(&[][..], |root| &root[..root.len() - 1]);
     if let Some(last) = source_file_path.last() {
         if last == MOD {
             source_file_path.pop();
         }
     }
+    if let [root] = source_file_path.as_slice() {
+        if CRATE_ROOTS.contains(&root.as_str()) {
+            source_file_path.pop();
+        }
+    }
+    if Some(source_file_path.as_slice()) == binary_root {
+        source_file_path.truncate(crate_key.len());
+    }
     if fst == pkg_name || fst == CRATE {
+        let crate_key = if fst == CRATE { crate_key } else { &[] };
         Dependency {
             path: DependencyPath(
-                dependency_components
+                crate_key
                     .iter()
-                    .skip(1)
+                    .chain(dependency_components.iter().skip(1))
                     .map(|s| s.into())
                     .collect::<Vec<_>>(),
             ),
@@ -60,6 +79,9 @@ pub fn expand_dependency(
             kind: DependencyKind::Relative,
         }
     } else {
+        if from_root {
+            source_file_path.truncate(crate_key.len());
+        }
         let source_file_path_len = source_file_path.len();
         source_file_path.append(
             &mut dependency_components
@@ -90,7 +112,7 @@ mod tests {
             String::from("foo"),
             String::from("bar"),
         ];
-        let result = expand_dependency(&dependency, "my_crate", vec![]);
+        let result = expand_dependency(&dependency, "my_crate", vec![], None, false);
         assert_eq!(
             result,
             Dependency {
@@ -107,7 +129,7 @@ mod tests {
             String::from("foo"),
             String::from("bar"),
         ];
-        let result = expand_dependency(&dependency, "my_crate", vec![]);
+        let result = expand_dependency(&dependency, "my_crate", vec![], None, false);
         assert_eq!(
             result,
             Dependency {
@@ -134,6 +156,8 @@ mod tests {
                 String::from("ccc"),
                 String::from("mod"),
             ],
+            None,
+            false,
         );
         assert_eq!(
             result,
@@ -159,6 +183,8 @@ mod tests {
             &dependency,
             "my_crate",
             vec![String::from("path"), String::from("mod")],
+            None,
+            false,
         );
         assert_eq!(
             result,
@@ -173,6 +199,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_belongs_to_a_submodule_of_the_crate_root() {
+        let dependency = vec![String::from("self"), String::from("foo")];
+        let result = expand_dependency(
+            &dependency,
+            "my_crate",
+            vec![String::from("lib")],
+            None,
+            false,
+        );
+        assert_eq!(
+            result,
+            Dependency {
+                path: DependencyPath(vec![String::from("foo")]),
+                kind: DependencyKind::Relative,
+            }
+        );
+    }
+
+    #[test]
+    fn it_belongs_to_the_crate_of_a_binary() {
+        let root = vec![
+            String::from("bin"),
+            String::from("tool"),
+            String::from("main"),
+        ];
+        let key = |names: &[&str]| DependencyPath(names.iter().map(|s| s.to_string()).collect());
+        for (dependency, expected) in [
+            (vec!["crate", "helper"], key(&["bin", "tool", "helper"])),
+            (vec!["self", "helper"], key(&["bin", "tool", "helper"])),
+            (vec!["my_crate", "config"], key(&["config"])),
+        ] {
+            let dependency = dependency.into_iter().map(String::from).collect::<Vec<_>>();
+            let result =
+                expand_dependency(&dependency, "my_crate", root.clone(), Some(&root), false);
+            assert_eq!(
+                result,
+                Dependency {
+                    path: expected,
+                    kind: DependencyKind::Relative,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn it_belongs_to_a_supermodule_of_an_inline_module() {
+        let dependency = vec![String::from("super"), String::from("foo")];
+        let result = expand_dependency(
+            &dependency,
+            "my_crate",
+            vec![String::from("aaa"), String::from("tests")],
+            None,
+            false,
+        );
+        assert_eq!(
+            result,
+            Dependency {
+                path: DependencyPath(vec![String::from("aaa"), String::from("foo")]),
+                kind: DependencyKind::Relative,
+            }
+        );
+    }
+
     #[test]
     fn it_may_belong_to_some_external_crate() {
         let dependency = vec![String::from("foo"), String::from("bar")];
@@ -180,6 +270,8 @@ mod tests {
             &dependency,
             "my_crate",
             vec![String::from("path"), String::from("mod")],
+            None,
+            false,
         );
         assert_eq!(
             result,
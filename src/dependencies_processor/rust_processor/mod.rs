@@ -15,4 +15,4 @@ enum DependencyKind {
 pub struct Dependency {
     path: DependencyPath,
     kind: DependencyKind,
-}
\ No newline at end of file
+}
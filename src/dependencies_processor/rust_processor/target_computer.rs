@@ -1,62 +1,396 @@
+use std::collections::BTreeSet as Set;
+
 use crate::{
-    dependencies::{DependencyPath, FilePath},
+    dependencies::{Dependency, DependencyPath, FilePath},
     dependencies_graph::DependenciesGraph,
-    dependencies_processor::DependencyProcessor,
+    dependencies_processor::{DependencyProcessor, Target},
+    package::{Edition, Package},
     trie::NodeKind,
 };
 
 use super::{dependency_expander::expand_dependency, DependencyKind, MOD};
 
 const LIB: &str = "lib";
+const MAIN: &str = "main";
+const CRATE: &str = "crate";
 
 pub struct RustDependencyProcessor {}
 
-impl DependencyProcessor for RustDependencyProcessor {
-    fn compute_target(
+impl RustDependencyProcessor {
+    /**
+     * The modules supplying the items of a glob import:
+     * a module re-exporting other modules by `pub use self::foo::*` hands the import over to them.
+     */
+    fn glob_suppliers(
+        trie: &DependenciesGraph,
+        target: Target,
+        package: &Package,
+        visited: &mut Set<FilePath>,
+    ) -> Vec<Target> {
+        let reexported_globs = trie
+            .get(&target.path.0)
+            .and_then(|node| node.value.as_ref())
+            .map(|module| {
+                module
+                    .dependencies
+                    .iter()
+                    .filter(|dependency| dependency.glob && dependency.reexported)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if reexported_globs.is_empty() || !visited.insert(target.path.clone()) {
+            return vec![target];
+        }
+        reexported_globs
+            .into_iter()
+            .flat_map(|dependency| {
+                let supplier = Self::compute_target(trie, &target.path, dependency, package);
+                Self::glob_suppliers(trie, supplier, package, visited)
+            })
+            .collect()
+    }
+
+    /**
+     * The root of the binary the module at `file_path` belongs to, when drawn along with the library:
+     * the module itself if it is a root, otherwise the one keyed under the longest directory holding the module.
+     */
+    fn binary_root<'a>(file_path: &[String], package: &'a Package) -> Option<&'a [String]> {
+        package
+            .binaries
+            .iter()
+            .filter(|root| file_path.starts_with(&root[..root.len() - 1]))
+            .max_by_key(|root| (root.len(), root.as_slice() == file_path))
+            .map(Vec::as_slice)
+    }
+
+    /**
+     * `visited` holds the facades already gone through, so that cyclic re-exports end.
+     */
+    fn resolve(
         trie: &DependenciesGraph,
         FilePath(file_path): &FilePath,
-        DependencyPath(dependency): &DependencyPath,
-        pkg_name: &str,
-    ) -> FilePath {
-        let dependency = expand_dependency(dependency, pkg_name, file_path.clone());
+        dependency: &Dependency,
+        package: &Package,
+        visited: &mut Set<FilePath>,
+    ) -> Target {
+        let from_root = dependency.rooted && package.edition == Edition::E2015;
+        // from the 2018 edition on, a path starting with `::` can only name an external crate
+        let external = dependency.global && package.edition >= Edition::E2018;
+        let binary_root = Self::binary_root(file_path, package);
+        let dependency = expand_dependency(
+            &dependency.path.0,
+            &package.name,
+            file_path.clone(),
+            binary_root,
+            from_root,
+        );
         let (longest_prefix, node_kind) = trie.get_longest_prefix(&dependency.path.0);
         let longest_prefix_len = longest_prefix.len();
         let mut longest_prefix = Vec::from(longest_prefix);
-        if node_kind == NodeKind::Internal {
+        if let Some(root) = binary_root.filter(|root| longest_prefix == root[..root.len() - 1]) {
+            longest_prefix = root.to_vec();
+        } else if node_kind == NodeKind::Internal
+            && trie
+                .get(&longest_prefix)
+                .is_some_and(|node| node.value.is_none())
+        {
+            // a module with submodules is either a `mod.rs` file, or a file next to a directory
             longest_prefix.push(MOD.into());
         }
-        let target = match dependency.kind {
+        let item = dependency.path.0.get(longest_prefix_len);
+        match dependency.kind {
             DependencyKind::Relative => {
-                if longest_prefix.is_empty() {
-                    vec![LIB.into()]
+                // the root of a binary, unless it shares the trie with the library
+                let root = if trie.children.contains_key(MAIN) && !trie.children.contains_key(LIB) {
+                    MAIN
+                } else {
+                    LIB
+                };
+                let path = FilePath(if longest_prefix.is_empty() {
+                    vec![root.into()]
                 } else {
                     longest_prefix
-                }
+                });
+                Self::follow_reexports(trie, path, item, package, visited)
             }
             DependencyKind::Ambiguous(source_file_path_len) => {
-                if longest_prefix_len <= source_file_path_len {
-                    // external dependency
-                    dependency.path.0[source_file_path_len..(source_file_path_len + 1)].into()
+                if external || longest_prefix_len <= source_file_path_len {
+                    // external dependency, possibly known under an alias
+                    let name = &dependency.path.0[source_file_path_len];
+                    match package.aliases.get(name) {
+                        Some(crate_name) => Target {
+                            path: FilePath(vec![crate_name.clone()]),
+                            alias: Some(name.clone()),
+                            item: None,
+                            via: vec![],
+                        },
+                        None => Target {
+                            path: FilePath(vec![name.clone()]),
+                            alias: None,
+                            item: None,
+                            via: vec![],
+                        },
+                    }
                 } else {
                     // inner relative dependency
-                    longest_prefix
+                    Self::follow_reexports(trie, FilePath(longest_prefix), item, package, visited)
+                }
+            }
+        }
+    }
+
+    /**
+     * An item re-exported by a facade module (`pub use self::bar::Widget;`)
+     * leads to the module it is imported from, the facade being kept as a hop.
+     */
+    fn follow_reexports(
+        trie: &DependenciesGraph,
+        path: FilePath,
+        item: Option<&String>,
+        package: &Package,
+        visited: &mut Set<FilePath>,
+    ) -> Target {
+        let reexport = package
+            .reexports
+            .as_ref()
+            .zip(item)
+            .and_then(|(reexports, item)| reexports.get(&path.0, item));
+        match reexport {
+            Some(reexport) if visited.insert(path.clone()) => {
+                // a re-export is declared by `use`
+                let reexport = Dependency {
+                    path: reexport.clone(),
+                    rooted: true,
+                    ..Dependency::default()
+                };
+                let mut target = Self::resolve(trie, &path, &reexport, package, visited);
+                target.via.insert(0, path);
+                target
+            }
+            _ => {
+                let item = item
+                    .filter(|item| {
+                        trie.get(&path.0)
+                            .and_then(|node| node.value.as_ref())
+                            .is_some_and(|module| module.items.contains_key(*item))
+                    })
+                    .cloned();
+                Target {
+                    path,
+                    alias: None,
+                    item,
+                    via: vec![],
                 }
             }
+        }
+    }
+
+    /**
+     * Like `compute_targets`, except that the unresolved imports are kept as external crates.
+     */
+    fn targets(
+        trie: &DependenciesGraph,
+        file_path: &FilePath,
+        dependency: &Dependency,
+        package: &Package,
+    ) -> Vec<Target> {
+        if let Some(targets) = Self::member_targets(trie, file_path, dependency, package) {
+            return targets;
+        }
+        let target = Self::compute_target(trie, file_path, dependency, package);
+        if dependency.glob {
+            Self::glob_suppliers(trie, target, package, &mut Set::new())
+        } else {
+            vec![target]
+        }
+    }
+
+    /**
+     * The crate the module at `file_path` belongs to, among the members of a workspace or the targets of a package.
+     */
+    fn crate_of<'a>(
+        trie: &'a DependenciesGraph,
+        file_path: &[String],
+        package: &'a Package,
+    ) -> (&'a DependenciesGraph, &'a Package) {
+        let member = file_path.split_first().and_then(|(name, rest)| {
+            Some((trie.children.get(name)?, rest, package.members.get(name)?))
+        });
+        match member {
+            Some((member_trie, rest, member)) => Self::crate_of(member_trie, rest, member),
+            None => (trie, package),
+        }
+    }
+
+    /**
+     * A path leading out of the crate is unresolved when the extern prelude is known and holds no such crate;
+     * the dev-dependencies are only known to the test-only dependencies.
+     */
+    fn is_unresolved(
+        trie: &DependenciesGraph,
+        target: &Target,
+        dependency: &Dependency,
+        package: &Package,
+    ) -> bool {
+        let Some(extern_crates) = &package.extern_crates else {
+            return false;
         };
-        FilePath(target)
+        match (target.path.0.as_slice(), &target.alias) {
+            ([name], None) => {
+                let known = trie.children.contains_key(name)
+                    || extern_crates.contains(name)
+                    || dependency.test_only && package.dev_crates.contains(name);
+                !known
+            }
+            _ => false,
+        }
+    }
+
+    /**
+     * The keys leading to the library named `name` among the members, through the targets of a member if need be.
+     */
+    fn library_key(package: &Package, name: &str) -> Option<Vec<String>> {
+        package.members.iter().find_map(|(key, member)| {
+            if member.members.is_empty() {
+                (member.name == name).then(|| vec![key.clone()])
+            } else {
+                Self::library_key(member, name).map(|inner| [vec![key.clone()], inner].concat())
+            }
+        })
+    }
+
+    /**
+     * In a workspace, a dependency is resolved within the member it is read in,
+     * whose modules are nested under its crate name; likewise within the targets of a package.
+     * An external crate which is the library of another member leads to the module of that library.
+     */
+    fn member_targets(
+        trie: &DependenciesGraph,
+        FilePath(file_path): &FilePath,
+        dependency: &Dependency,
+        workspace: &Package,
+    ) -> Option<Vec<Target>> {
+        let (name, file_path) = file_path.split_first()?;
+        let package = workspace.members.get(name)?;
+        let member_trie = trie.get(std::slice::from_ref(name))?;
+        let prefix = |FilePath(path): FilePath| {
+            FilePath([std::slice::from_ref(name), path.as_slice()].concat())
+        };
+        let targets = Self::targets(
+            member_trie,
+            &FilePath(file_path.to_vec()),
+            dependency,
+            package,
+        );
+        let targets = targets.into_iter().flat_map(|target| {
+            let internal = target
+                .path
+                .0
+                .first()
+                .is_some_and(|first| first == LIB || member_trie.children.contains_key(first));
+            if internal {
+                return vec![Target {
+                    path: prefix(target.path),
+                    via: target.via.into_iter().map(prefix).collect(),
+                    ..target
+                }];
+            }
+            let (sibling, key) = match target.path.0.as_slice() {
+                [sibling] => match Self::library_key(workspace, sibling) {
+                    Some(key) => (sibling, key),
+                    None => return vec![target],
+                },
+                _ => return vec![target],
+            };
+            // the path within the sibling, unless the crate was reached through a re-export
+            let local_name = target.alias.as_ref().unwrap_or(sibling);
+            let rest = match dependency.path.0.split_first() {
+                Some((first, rest)) if first == local_name => rest,
+                _ => &[],
+            };
+            let dependency = Dependency {
+                path: DependencyPath([&[String::from(CRATE)], rest].concat()),
+                rooted: false,
+                ..dependency.clone()
+            };
+            Self::member_targets(trie, &FilePath(key), &dependency, workspace)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|sibling_target| Target {
+                    alias: target.alias.clone(),
+                    ..sibling_target
+                })
+                .collect()
+        });
+        Some(targets.collect())
+    }
+}
+
+impl DependencyProcessor for RustDependencyProcessor {
+    fn compute_target(
+        trie: &DependenciesGraph,
+        file_path: &FilePath,
+        dependency: &Dependency,
+        package: &Package,
+    ) -> Target {
+        Self::resolve(trie, file_path, dependency, package, &mut Set::new())
+    }
+
+    /**
+     * The imports naming no known crate are left out; `unresolved_imports` lists them.
+     */
+    fn compute_targets(
+        trie: &DependenciesGraph,
+        file_path: &FilePath,
+        dependency: &Dependency,
+        package: &Package,
+    ) -> Vec<Target> {
+        let (crate_trie, crate_package) = Self::crate_of(trie, &file_path.0, package);
+        Self::targets(trie, file_path, dependency, package)
+            .into_iter()
+            .filter(|target| !Self::is_unresolved(crate_trie, target, dependency, crate_package))
+            .collect()
+    }
+}
+
+impl RustDependencyProcessor {
+    /**
+     * The imports naming no known crate, as paths along with the path of their module in the whole graph.
+     * Each one is listed once, however many items of the module use it.
+     */
+    pub fn unresolved_imports(
+        trie: &DependenciesGraph,
+        package: &Package,
+    ) -> Set<(String, String)> {
+        let mut unresolved = Set::new();
+        trie.for_each_entry(&mut |path, module| {
+            let file_path = FilePath(path.to_vec());
+            let (crate_trie, crate_package) = Self::crate_of(trie, path, package);
+            let items = module.items.values().flatten();
+            for dependency in module.dependencies.iter().chain(items) {
+                let targets = Self::targets(trie, &file_path, dependency, package);
+                if targets.iter().any(|target| {
+                    Self::is_unresolved(crate_trie, target, dependency, crate_package)
+                }) {
+                    unresolved.insert((dependency.path.0.join("::"), path.join("::")));
+                }
+            }
+        });
+        unresolved
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap as Map;
+    use std::collections::{BTreeMap as Map, BTreeSet as Set, VecDeque};
 
     use crate::{
-        dependencies_graph::DependenciesGraph,
-        dependencies::{DependencyPath, FilePath},
+        dependencies::{Dependency, DependencyPath, FilePath},
+        dependencies_graph::{DependenciesGraph, Module, Reexports},
         dependencies_processor::{
-            rust_processor::target_computer::RustDependencyProcessor, DependencyProcessor,
+            rust_processor::target_computer::RustDependencyProcessor, DependencyProcessor, Target,
         },
+        package::{Edition, Package},
     };
 
     fn make_trie() -> DependenciesGraph {
@@ -114,10 +448,11 @@ mod tests {
     fn it_targets_lib() {
         let trie = make_trie();
         let file_path = FilePath(vec![String::from("foo"), String::from("bar")]);
-        let dependency = DependencyPath(vec![String::from("crate"), String::from("Widget")]);
-        let pkg_name = "my_crate";
+        let dependency: Dependency =
+            DependencyPath(vec![String::from("crate"), String::from("Widget")]).into();
+        let package = Package::new("my_crate");
         assert_eq!(
-            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, pkg_name),
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path,
             FilePath(vec![String::from("lib")])
         );
     }
@@ -126,14 +461,15 @@ mod tests {
     fn it_targets_mod() {
         let trie = make_trie();
         let file_path = FilePath(vec![String::from("abc")]);
-        let dependency = DependencyPath(vec![
+        let dependency: Dependency = DependencyPath(vec![
             String::from("crate"),
             String::from("foo"),
             String::from("Widget"),
-        ]);
-        let pkg_name = "my_crate";
+        ])
+        .into();
+        let package = Package::new("my_crate");
         assert_eq!(
-            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, pkg_name),
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path,
             FilePath(vec![String::from("foo"), String::from("mod")])
         );
     }
@@ -142,10 +478,11 @@ mod tests {
     fn it_targets_some_child_directly() {
         let trie = make_trie();
         let file_path = FilePath(vec![String::from("foo"), String::from("mod")]);
-        let dependency = DependencyPath(vec![String::from("bar"), String::from("baz")]);
-        let pkg_name = "my_crate";
+        let dependency: Dependency =
+            DependencyPath(vec![String::from("bar"), String::from("baz")]).into();
+        let package = Package::new("my_crate");
         assert_eq!(
-            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, pkg_name),
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path,
             FilePath(vec![String::from("foo"), String::from("bar")])
         );
     }
@@ -154,14 +491,15 @@ mod tests {
     fn it_targets_some_child_via_self() {
         let trie = make_trie();
         let file_path = FilePath(vec![String::from("foo"), String::from("mod")]);
-        let dependency = DependencyPath(vec![
+        let dependency: Dependency = DependencyPath(vec![
             String::from("self"),
             String::from("bar"),
             String::from("baz"),
-        ]);
-        let pkg_name = "my_crate";
+        ])
+        .into();
+        let package = Package::new("my_crate");
         assert_eq!(
-            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, pkg_name),
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path,
             FilePath(vec![String::from("foo"), String::from("bar")])
         );
     }
@@ -170,14 +508,15 @@ mod tests {
     fn it_targets_some_sibling_via_pkg_name() {
         let trie = make_trie();
         let file_path = FilePath(vec![String::from("foo"), String::from("bar")]);
-        let dependency = DependencyPath(vec![
+        let dependency: Dependency = DependencyPath(vec![
             String::from("my_crate"),
             String::from("abc"),
             String::from("def"),
-        ]);
-        let pkg_name = "my_crate";
+        ])
+        .into();
+        let package = Package::new("my_crate");
         assert_eq!(
-            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, pkg_name),
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path,
             FilePath(vec![String::from("abc")])
         );
     }
@@ -186,14 +525,15 @@ mod tests {
     fn it_targets_some_sibling_via_crate() {
         let trie = make_trie();
         let file_path = FilePath(vec![String::from("foo"), String::from("bar")]);
-        let dependency = DependencyPath(vec![
+        let dependency: Dependency = DependencyPath(vec![
             String::from("crate"),
             String::from("abc"),
             String::from("def"),
-        ]);
-        let pkg_name = "my_crate";
+        ])
+        .into();
+        let package = Package::new("my_crate");
         assert_eq!(
-            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, pkg_name),
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path,
             FilePath(vec![String::from("abc")])
         );
     }
@@ -202,28 +542,405 @@ mod tests {
     fn it_targets_some_sibling_via_super() {
         let trie = make_trie();
         let file_path = FilePath(vec![String::from("foo"), String::from("bar")]);
-        let dependency = DependencyPath(vec![
+        let dependency: Dependency = DependencyPath(vec![
             String::from("super"),
             String::from("super"),
             String::from("abc"),
             String::from("def"),
-        ]);
-        let pkg_name = "my_crate";
+        ])
+        .into();
+        let package = Package::new("my_crate");
         assert_eq!(
-            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, pkg_name),
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path,
             FilePath(vec![String::from("abc")])
         );
     }
 
+    #[test]
+    fn it_targets_a_module_with_inline_submodules() {
+        let mut trie = make_trie();
+        trie.insert(VecDeque::from([String::from("abc")]), Module::default());
+        trie.insert(
+            VecDeque::from([String::from("abc"), String::from("tests")]),
+            Module::default(),
+        );
+        let file_path = FilePath(vec![String::from("abc"), String::from("tests")]);
+        let dependency: Dependency =
+            DependencyPath(vec![String::from("super"), String::from("Widget")]).into();
+        let package = Package::new("my_crate");
+        assert_eq!(
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path,
+            FilePath(vec![String::from("abc")])
+        );
+    }
+
+    #[test]
+    fn it_resolves_use_paths_from_the_crate_root_in_the_2015_edition() {
+        let trie = make_trie();
+        let file_path = FilePath(vec![String::from("foo"), String::from("bar")]);
+        let target = |components: &[&str], rooted, edition| {
+            let dependency = Dependency {
+                rooted,
+                ..DependencyPath(components.iter().map(|s| s.to_string()).collect()).into()
+            };
+            let package = Package {
+                edition,
+                ..Package::new("my_crate")
+            };
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path
+        };
+        let module = FilePath(vec![String::from("foo"), String::from("mod")]);
+        let external = FilePath(vec![String::from("foo")]);
+        assert_eq!(target(&["foo", "Widget"], true, Edition::E2015), module);
+        assert_eq!(target(&["foo", "Widget"], true, Edition::E2018), external);
+        assert_eq!(target(&["foo", "Widget"], false, Edition::E2015), external);
+        assert_eq!(
+            target(&["std", "fmt"], true, Edition::E2015),
+            FilePath(vec![String::from("std")])
+        );
+        assert_eq!(
+            target(&["self", "baz"], true, Edition::E2015),
+            FilePath(vec![String::from("foo"), String::from("bar")])
+        );
+    }
+
+    #[test]
+    fn it_resolves_leading_colons_to_an_external_crate_from_the_2018_edition() {
+        let trie = make_trie();
+        let file_path = FilePath(vec![String::from("lib")]);
+        let target = |global, edition| {
+            let dependency = Dependency {
+                rooted: global,
+                global,
+                ..DependencyPath(vec![String::from("foo"), String::from("Widget")]).into()
+            };
+            let package = Package {
+                edition,
+                ..Package::new("my_crate")
+            };
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path
+        };
+        // the module `foo` of the crate shadows the crate `foo`, but for `::foo`
+        let module = FilePath(vec![String::from("foo"), String::from("mod")]);
+        let external = FilePath(vec![String::from("foo")]);
+        assert_eq!(target(false, Edition::E2021), module);
+        assert_eq!(target(true, Edition::E2021), external);
+        assert_eq!(target(true, Edition::E2018), external);
+        assert_eq!(target(true, Edition::E2015), module);
+    }
+
     #[test]
     fn it_targets_an_external_dependency() {
         let trie = make_trie();
         let file_path = FilePath(vec![String::from("abc")]);
-        let dependency = DependencyPath(vec![String::from("std")]);
-        let pkg_name = "my_crate";
+        let dependency: Dependency = DependencyPath(vec![String::from("std")]).into();
+        let package = Package::new("my_crate");
         assert_eq!(
-            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, pkg_name),
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path,
             FilePath(vec![String::from("std")])
         );
     }
+
+    #[test]
+    fn it_targets_the_module_of_another_member_of_the_workspace() {
+        let mut trie = DependenciesGraph::new();
+        for path in [["app", "main"], ["core", "lib"], ["core", "order"]] {
+            trie.insert(VecDeque::from(path.map(String::from)), Module::default());
+        }
+        let workspace = Package {
+            members: Map::from([
+                (String::from("app"), Package::new("app")),
+                (String::from("core"), Package::new("core")),
+            ]),
+            ..Package::default()
+        };
+        let file_path = FilePath(vec![String::from("app"), String::from("main")]);
+        let targets = |components: &[&str]| {
+            let dependency: Dependency =
+                DependencyPath(components.iter().map(|s| s.to_string()).collect()).into();
+            RustDependencyProcessor::compute_targets(&trie, &file_path, &dependency, &workspace)
+                .into_iter()
+                .map(|target| target.path)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            targets(&["core", "order", "Order"]),
+            vec![FilePath(vec![String::from("core"), String::from("order")])]
+        );
+        assert_eq!(
+            targets(&["core"]),
+            vec![FilePath(vec![String::from("core"), String::from("lib")])]
+        );
+        assert_eq!(
+            targets(&["serde", "Serialize"]),
+            vec![FilePath(vec![String::from("serde")])]
+        );
+    }
+
+    #[test]
+    fn it_targets_the_library_of_the_package_from_its_binary() {
+        let mut trie = DependenciesGraph::new();
+        for path in [
+            ["app", "bin_app", "main"],
+            ["app", "lib_app", "lib"],
+            ["app", "lib_app", "config"],
+            ["core", "order", "mod"],
+        ] {
+            trie.insert(VecDeque::from(path.map(String::from)), Module::default());
+        }
+        let app = Package {
+            name: String::from("app"),
+            members: Map::from([
+                (String::from("bin_app"), Package::default()),
+                (String::from("lib_app"), Package::new("app")),
+            ]),
+            ..Package::default()
+        };
+        let workspace = Package {
+            members: Map::from([
+                (String::from("app"), app),
+                (String::from("core"), Package::new("core")),
+            ]),
+            ..Package::default()
+        };
+        let file_path = FilePath(vec![
+            String::from("app"),
+            String::from("bin_app"),
+            String::from("main"),
+        ]);
+        let targets = |components: &[&str]| {
+            let dependency: Dependency =
+                DependencyPath(components.iter().map(|s| s.to_string()).collect()).into();
+            RustDependencyProcessor::compute_targets(&trie, &file_path, &dependency, &workspace)
+                .into_iter()
+                .map(|target| target.path)
+                .collect::<Vec<_>>()
+        };
+        let make_path =
+            |components: &[&str]| FilePath(components.iter().map(|s| s.to_string()).collect());
+        assert_eq!(
+            targets(&["app", "config", "Config"]),
+            vec![make_path(&["app", "lib_app", "config"])]
+        );
+        assert_eq!(
+            targets(&["crate", "Cli"]),
+            vec![make_path(&["app", "bin_app", "main"])]
+        );
+        assert_eq!(
+            targets(&["core", "order", "Order"]),
+            vec![make_path(&["core", "order", "mod"])]
+        );
+    }
+
+    #[test]
+    fn it_leaves_out_the_imports_of_unknown_crates() {
+        let trie = make_trie();
+        let file_path = FilePath(vec![String::from("foo"), String::from("bar")]);
+        let package = Package {
+            extern_crates: Some(Set::from([String::from("serde")])),
+            ..Package::new("my_crate")
+        };
+        let targets = |components: &[&str]| {
+            let dependency: Dependency =
+                DependencyPath(components.iter().map(|s| s.to_string()).collect()).into();
+            RustDependencyProcessor::compute_targets(&trie, &file_path, &dependency, &package)
+                .into_iter()
+                .map(|target| target.path)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            targets(&["serde", "Serialize"]),
+            vec![FilePath(vec![String::from("serde")])]
+        );
+        assert_eq!(
+            targets(&["foo", "Foo"]),
+            vec![FilePath(vec![String::from("foo")])]
+        );
+        assert!(targets(&["unknown", "Thing"]).is_empty());
+    }
+
+    #[test]
+    fn it_lists_each_unresolved_import_once() {
+        let mut trie = make_trie();
+        let unknown: Dependency =
+            DependencyPath(vec![String::from("unknown"), String::from("Thing")]).into();
+        let serde: Dependency =
+            DependencyPath(vec![String::from("serde"), String::from("Serialize")]).into();
+        trie.insert(
+            VecDeque::from([String::from("abc")]),
+            Module {
+                dependencies: vec![unknown.clone(), serde],
+                file: None,
+                items: Map::from([
+                    (String::from("first"), vec![unknown.clone()]),
+                    (String::from("second"), vec![unknown]),
+                ]),
+            },
+        );
+        let package = Package {
+            extern_crates: Some(Set::from([String::from("serde")])),
+            ..Package::new("my_crate")
+        };
+        assert_eq!(
+            RustDependencyProcessor::unresolved_imports(&trie, &package),
+            Set::from([(String::from("unknown::Thing"), String::from("abc"))])
+        );
+    }
+
+    #[test]
+    fn it_leaves_the_dev_dependencies_to_the_tests() {
+        let trie = make_trie();
+        let file_path = FilePath(vec![String::from("foo"), String::from("bar")]);
+        let package = Package {
+            extern_crates: Some(Set::new()),
+            dev_crates: Set::from([String::from("proptest")]),
+            ..Package::new("my_crate")
+        };
+        let targets = |test_only| {
+            let dependency = Dependency {
+                test_only,
+                ..DependencyPath(vec![String::from("proptest"), String::from("Strategy")]).into()
+            };
+            RustDependencyProcessor::compute_targets(&trie, &file_path, &dependency, &package)
+        };
+        assert!(targets(false).is_empty());
+        assert_eq!(targets(true).len(), 1);
+    }
+
+    #[test]
+    fn it_targets_the_package_of_an_alias() {
+        let trie = make_trie();
+        let file_path = FilePath(vec![String::from("abc")]);
+        let dependency: Dependency =
+            DependencyPath(vec![String::from("json"), String::from("Value")]).into();
+        let mut package = Package::new("my_crate");
+        package
+            .aliases
+            .insert(String::from("json"), String::from("serde_json"));
+        assert_eq!(
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package),
+            Target {
+                path: FilePath(vec![String::from("serde_json")]),
+                alias: Some(String::from("json")),
+                item: None,
+                via: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn it_targets_the_suppliers_of_a_glob_import() {
+        let mut trie = make_trie();
+        trie.insert(
+            VecDeque::from([String::from("foo"), String::from("mod")]),
+            Module {
+                dependencies: vec![Dependency {
+                    reexported: true,
+                    ..DependencyPath(vec![
+                        String::from("self"),
+                        String::from("bar"),
+                        String::from("*"),
+                    ])
+                    .into()
+                }],
+                file: None,
+                items: Map::new(),
+            },
+        );
+        let file_path = FilePath(vec![String::from("abc")]);
+        let dependency = DependencyPath(vec![
+            String::from("crate"),
+            String::from("foo"),
+            String::from("*"),
+        ])
+        .into();
+        let package = Package::new("my_crate");
+        assert_eq!(
+            RustDependencyProcessor::compute_targets(&trie, &file_path, &dependency, &package),
+            vec![Target {
+                path: FilePath(vec![String::from("foo"), String::from("bar")]),
+                alias: None,
+                item: None,
+                via: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn it_follows_reexports_if_requested() {
+        let mut trie = make_trie();
+        trie.insert(
+            VecDeque::from([String::from("foo"), String::from("mod")]),
+            Module {
+                dependencies: vec![Dependency {
+                    reexported: true,
+                    ..DependencyPath(vec![
+                        String::from("self"),
+                        String::from("bar"),
+                        String::from("Widget"),
+                    ])
+                    .into()
+                }],
+                file: None,
+                items: Map::new(),
+            },
+        );
+        let file_path = FilePath(vec![String::from("abc")]);
+        let dependency: Dependency = DependencyPath(vec![
+            String::from("crate"),
+            String::from("foo"),
+            String::from("Widget"),
+        ])
+        .into();
+        let mut package = Package::new("my_crate");
+        assert_eq!(
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package).path,
+            FilePath(vec![String::from("foo"), String::from("mod")])
+        );
+        package.reexports = Some(Reexports::new(&trie));
+        assert_eq!(
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package),
+            Target {
+                path: FilePath(vec![String::from("foo"), String::from("bar")]),
+                alias: None,
+                item: None,
+                via: vec![FilePath(vec![String::from("foo"), String::from("mod")])],
+            }
+        );
+    }
+
+    #[test]
+    fn it_targets_an_item_of_a_module() {
+        let mut trie = make_trie();
+        trie.insert(
+            VecDeque::from([String::from("foo"), String::from("bar")]),
+            Module {
+                dependencies: vec![],
+                file: None,
+                items: Map::from([(String::from("Widget"), vec![])]),
+            },
+        );
+        let file_path = FilePath(vec![String::from("abc")]);
+        let package = Package::new("my_crate");
+        let target = |name: &str| {
+            let dependency: Dependency = DependencyPath(vec![
+                String::from("crate"),
+                String::from("foo"),
+                String::from("bar"),
+                String::from(name),
+            ])
+            .into();
+            RustDependencyProcessor::compute_target(&trie, &file_path, &dependency, &package)
+        };
+        assert_eq!(
+            target("Widget"),
+            Target {
+                path: FilePath(vec![String::from("foo"), String::from("bar")]),
+                alias: None,
+                item: Some(String::from("Widget")),
+                via: vec![],
+            }
+        );
+        assert_eq!(target("Gadget").item, None);
+    }
 }
@@ -3,35 +3,468 @@
  * This file is part of Cargo Graphmod.
  * SPDX-License-Identifier: GPL-3.0-only
  */
-use std::{collections::VecDeque, fs::read_to_string, io::Result, path::Path};
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set, VecDeque},
+    fs::read_to_string,
+    io::Result,
+    path::{Path, PathBuf},
+};
 
-use crate::{dependencies_graph::DependenciesGraph, parser::Parser};
+use crate::{
+    cfg::CfgSet,
+    dependencies::{Dependency, DependencyPath, EdgeKind},
+    dependencies_graph::{DependenciesGraph, Module},
+    parser::{ParsedModule, Parser},
+};
 
 const EXTENSION: &str = "rs";
+const MOD: &str = "mod";
+const CRATE: &str = "crate";
 
+pub fn is_rust_file(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|e| e == EXTENSION)
+}
+
+/**
+ * `foo.rs` or `foo/mod.rs`, as rustc looks for them.
+ */
+fn find_module_file(directory: &Path, name: &str) -> Option<PathBuf> {
+    [
+        directory.join(name).with_extension(EXTENSION),
+        directory.join(name).join(MOD).with_extension(EXTENSION),
+    ]
+    .into_iter()
+    .find(|path| is_rust_file(path))
+}
+
+/**
+ * Where a module stands in the trie, and where the files of its submodules are to be found.
+ * Both usually follow from each other, except for the modules loaded through a `#[path]` attribute,
+ * which are keyed by their declaration.
+ */
+struct Location {
+    key: Vec<String>,
+    children_key: Vec<String>,
+    children_directory: PathBuf,
+    /**
+     * The directory `#[path]` attributes are relative to.
+     */
+    attributes_directory: PathBuf,
+}
+
+impl Location {
+    /**
+     * The root of a crate is keyed by `key`, and its submodules are found next to it.
+     */
+    fn of_crate_root(file: &Path, key: &[String]) -> Self {
+        let directory = file.parent().unwrap_or(Path::new("")).to_path_buf();
+        Location {
+            key: key.to_vec(),
+            children_key: key[..key.len() - 1].to_vec(),
+            children_directory: directory.clone(),
+            attributes_directory: directory,
+        }
+    }
+
+    fn of_inline_module(&self, name: &str) -> Self {
+        let key = [self.children_key.as_slice(), &[name.to_string()]].concat();
+        let directory = self.children_directory.join(name);
+        Location {
+            key: key.clone(),
+            children_key: key,
+            children_directory: directory.clone(),
+            attributes_directory: directory,
+        }
+    }
+
+    /**
+     * `foo.rs` and `foo/mod.rs` keep their file path as key,
+     * whereas a file given by a `#[path]` attribute behaves like a `mod.rs` file named after the module.
+     */
+    fn of_file_module(&self, name: &str, file: &Path, has_path_attribute: bool) -> Self {
+        let mut location = self.of_inline_module(name);
+        let directory = file.parent().unwrap_or(&self.children_directory);
+        if has_path_attribute {
+            location.children_directory = directory.to_path_buf();
+        } else if file.file_stem().is_some_and(|stem| stem == MOD) {
+            location.key.push(MOD.to_string());
+        }
+        location.attributes_directory = directory.to_path_buf();
+        location
+    }
+}
+
+/**
+ * The trie keys of the modules defining the macros in scope, by name.
+ */
+type MacroScope = Map<String, Vec<String>>;
+
+/**
+ * The path of a macro defined in the module keyed by `key`, as the dependency processor resolves it.
+ */
+fn macro_path(key: &[String], name: &str) -> DependencyPath {
+    DependencyPath([&[CRATE.to_string()], key, &[name.to_string()]].concat())
+}
+
+/**
+ * Macros invoked by their name alone are looked up in the textual scope; those not found come from other crates.
+ * The spans of the dependencies are completed with the file they come from.
+ */
+fn resolve_in_scope(
+    dependencies: Vec<Dependency>,
+    scope: &MacroScope,
+    test_only: bool,
+    file: &Path,
+) -> Vec<Dependency> {
+    dependencies
+        .into_iter()
+        .filter_map(|mut dependency| {
+            if dependency.kind == EdgeKind::Macro {
+                if let [name] = dependency.path.0.as_slice() {
+                    dependency.path = macro_path(scope.get(name)?, name);
+                }
+            }
+            dependency.test_only |= test_only;
+            if let Some(span) = &mut dependency.span {
+                span.file = file.to_path_buf();
+            }
+            Some(dependency)
+        })
+        .collect()
+}
+
+struct ModuleTreeBuilder<'a> {
+    trie: &'a mut DependenciesGraph,
+    aliases: &'a mut Map<String, String>,
+    tests: bool,
+    cfgs: &'a CfgSet,
+    visited: &'a mut Set<PathBuf>,
+    /** The macros annotated with `#[macro_export]`, which can be invoked by `crate::name!`. */
+    exported_macros: MacroScope,
+}
+
+impl<'a> ModuleTreeBuilder<'a> {
+    fn add_file<LanguageParser: Parser>(
+        &mut self,
+        file: &Path,
+        location: &Location,
+        has_path_attribute: bool,
+        scope: &MacroScope,
+        test_only: bool,
+    ) -> Result<MacroScope> {
+        self.visited.insert(file.to_path_buf());
+        let contents = read_to_string(file)?;
+        let module = LanguageParser::parse_module(&contents, self.cfgs);
+        if let Some(error) = &module.fallback {
+            eprintln!(
+                "Warning: unable to parse {} ({}); falling back to regular expressions.",
+                file.display(),
+                error
+            );
+        }
+        self.add_module::<LanguageParser>(
+            file,
+            location,
+            module,
+            has_path_attribute.then(|| file.to_path_buf()),
+            scope,
+            test_only,
+        )
+    }
+
+    /**
+     * The macros of a module are in scope in its submodules, as well as those of its `#[macro_use]` submodules,
+     * regardless of the order of the declarations.
+     * Returns the macros that a `#[macro_use]` annotation on this module would bring into its parent.
+     */
+    fn add_module<LanguageParser: Parser>(
+        &mut self,
+        file: &Path,
+        location: &Location,
+        module: ParsedModule,
+        path_attribute_file: Option<PathBuf>,
+        inherited_scope: &MacroScope,
+        test_only: bool,
+    ) -> Result<MacroScope> {
+        self.aliases.extend(module.aliases);
+        let mut own_macros = MacroScope::new();
+        for definition in module.macros {
+            if definition.exported {
+                self.exported_macros
+                    .insert(definition.name.clone(), location.key.clone());
+            }
+            own_macros.insert(definition.name, location.key.clone());
+        }
+        let mut scope = inherited_scope.clone();
+        scope.extend(own_macros.clone());
+        for declaration in module.submodules {
+            let skipped = (declaration.test_only && !self.tests) || declaration.disabled;
+            let test_only = test_only || declaration.test_only;
+            let module_file = match &declaration.path {
+                Some(path) => {
+                    Some(location.attributes_directory.join(path)).filter(|path| is_rust_file(path))
+                }
+                None => find_module_file(&location.children_directory, &declaration.name),
+            };
+            match (declaration.contents, skipped) {
+                (Some(_), true) => {}
+                (Some(contents), false) => {
+                    let macros = self.add_module::<LanguageParser>(
+                        file,
+                        &location.of_inline_module(&declaration.name),
+                        contents,
+                        None,
+                        &scope,
+                        test_only,
+                    )?;
+                    if declaration.macro_use {
+                        own_macros.extend(macros.clone());
+                        scope.extend(macros);
+                    }
+                }
+                (None, _) => match module_file {
+                    Some(path) if skipped => {
+                        self.visited.insert(path);
+                    }
+                    None if skipped => {}
+                    None => eprintln!(
+                        "Warning: unable to find the file of module {} declared in {}.",
+                        declaration.name,
+                        file.display()
+                    ),
+                    Some(path) if self.visited.contains(&path) => {}
+                    Some(path) => {
+                        let has_path_attribute = declaration.path.is_some();
+                        let macros = self.add_file::<LanguageParser>(
+                            &path,
+                            &location.of_file_module(&declaration.name, &path, has_path_attribute),
+                            has_path_attribute,
+                            &scope,
+                            test_only,
+                        )?;
+                        if declaration.macro_use {
+                            own_macros.extend(macros.clone());
+                            scope.extend(macros);
+                        }
+                    }
+                },
+            }
+        }
+        let items = module
+            .items
+            .into_iter()
+            .map(|(name, dependencies)| {
+                (
+                    name,
+                    resolve_in_scope(dependencies, &scope, test_only, file),
+                )
+            })
+            .collect();
+        self.trie.insert(
+            location.key.iter().cloned().collect::<VecDeque<_>>(),
+            Module {
+                dependencies: resolve_in_scope(module.dependencies, &scope, test_only, file),
+                file: path_attribute_file,
+                items,
+            },
+        );
+        Ok(own_macros)
+    }
+
+    /**
+     * `crate::name!` invokes a macro exported at the root of the crate.
+     */
+    fn resolve_exported_macros(&mut self) {
+        let exported_macros = &self.exported_macros;
+        self.trie.for_each_value_mut(&mut |module| {
+            let items = module.items.values_mut().flatten();
+            for dependency in module.dependencies.iter_mut().chain(items) {
+                if dependency.kind != EdgeKind::Macro {
+                    continue;
+                }
+                if let [root, name] = dependency.path.0.as_slice() {
+                    if let Some(key) = exported_macros.get(name).filter(|_| root == CRATE) {
+                        dependency.path = macro_path(key, name);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/**
+ * Follows the `mod` declarations from the roots of the crates, each keyed as given, as rustc does.
+ * Dependencies of the modules declared under `#[cfg(test)]` are only read if `tests` is set,
+ * and the modules compiled out according to `cfgs` are skipped.
+ * The aliases of `extern crate` declarations are collected along the way,
+ * and the macro invocations are resolved to the modules defining the macros.
+ * The files read are added to `visited`.
+ */
 pub fn build_dependencies_trie<LanguageParser: Parser>(
-    path: &Path,
+    roots: &[(PathBuf, Vec<String>)],
     trie: &mut DependenciesGraph,
-    skip_length: usize,
+    aliases: &mut Map<String, String>,
+    tests: bool,
+    cfgs: &CfgSet,
+    visited: &mut Set<PathBuf>,
 ) -> Result<()> {
-    if path.is_file() {
-        if let Some(Some(EXTENSION)) = path.extension().map(|e| e.to_str()) {
-            let contents = read_to_string(path)?;
-            let components = path
-                .with_extension("")
-                .iter()
-                .skip(skip_length)
-                .map(|s| s.to_string_lossy().into())
-                .collect::<VecDeque<_>>();
-            let dependencies = LanguageParser::parse_dependencies(&contents);
-            trie.insert(components.clone(), dependencies);
-        }
-    } else if path.is_dir() {
+    let mut builder = ModuleTreeBuilder {
+        trie,
+        aliases,
+        tests,
+        cfgs,
+        visited,
+        exported_macros: MacroScope::new(),
+    };
+    for (root, key) in roots {
+        let location = Location::of_crate_root(root, key);
+        builder.add_file::<LanguageParser>(root, &location, false, &MacroScope::new(), false)?;
+    }
+    builder.resolve_exported_macros();
+    Ok(())
+}
+
+/**
+ * Reports the Rust files under `path` which have not been read.
+ */
+pub fn report_stray_files(path: &Path, visited: &Set<PathBuf>) {
+    if path.is_dir() {
         for entry in path.read_dir().expect("read_dir call failed").flatten() {
-            build_dependencies_trie::<LanguageParser>(&entry.path(), trie, skip_length)?;
+            report_stray_files(&entry.path(), visited);
         }
-    } else {
-        read_to_string(path)?;
+    } else if is_rust_file(path) && !visited.contains(path) {
+        eprintln!(
+            "Warning: {} is not declared by any module; it has been ignored.",
+            path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap as Map, BTreeSet as Set},
+        path::PathBuf,
+    };
+
+    use crate::{
+        cfg::CfgSet,
+        dependencies::{DependencyPath, EdgeKind},
+        dependencies_graph::DependenciesGraph,
+        files_reader::build_dependencies_trie,
+        parser::rust_parser::RustParser,
+    };
+
+    #[test]
+    fn it_follows_module_declarations() {
+        let mut trie = DependenciesGraph::new();
+        build_dependencies_trie::<RustParser>(
+            &[(
+                PathBuf::from("tests/web_app/src/lib.rs"),
+                vec![String::from("lib")],
+            )],
+            &mut trie,
+            &mut Map::new(),
+            false,
+            &CfgSet::default(),
+            &mut Set::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            trie.children.keys().collect::<Vec<_>>(),
+            vec![
+                "app_builder",
+                "configuration",
+                "domain",
+                "interfaces",
+                "lib",
+                "storage",
+                "use_cases"
+            ]
+        );
+        assert_eq!(
+            trie.children["storage"].children.keys().collect::<Vec<_>>(),
+            vec!["mod", "postgres_db", "sqlite_db"]
+        );
+    }
+
+    #[test]
+    fn it_keys_path_attributes_by_module_name() {
+        let mut trie = DependenciesGraph::new();
+        build_dependencies_trie::<RustParser>(
+            &[(
+                PathBuf::from("tests/path_attributes/src/lib.rs"),
+                vec![String::from("lib")],
+            )],
+            &mut trie,
+            &mut Map::new(),
+            false,
+            &CfgSet::default(),
+            &mut Set::new(),
+        )
+        .unwrap();
+        let module = trie.children["storage"].children["memory_db"]
+            .value
+            .as_ref()
+            .unwrap();
+        assert_eq!(
+            module.file,
+            Some(PathBuf::from(
+                "tests/path_attributes/src/storage/in_memory/db.rs"
+            ))
+        );
+        assert!(trie.children["storage"].children["mod"]
+            .value
+            .as_ref()
+            .unwrap()
+            .file
+            .is_none());
+    }
+
+    #[test]
+    fn it_resolves_macros_to_their_definitions() {
+        let mut trie = DependenciesGraph::new();
+        build_dependencies_trie::<RustParser>(
+            &[(
+                PathBuf::from("tests/macros/src/lib.rs"),
+                vec![String::from("lib")],
+            )],
+            &mut trie,
+            &mut Map::new(),
+            false,
+            &CfgSet::default(),
+            &mut Set::new(),
+        )
+        .unwrap();
+        let macros_of = |module: &DependenciesGraph| {
+            module
+                .value
+                .as_ref()
+                .unwrap()
+                .dependencies
+                .iter()
+                .filter(|dependency| dependency.kind == EdgeKind::Macro)
+                .map(|dependency| dependency.path.clone())
+                .collect::<Vec<_>>()
+        };
+        let make_path = |name: &str| {
+            DependencyPath(vec![
+                String::from("crate"),
+                String::from("macros"),
+                String::from(name),
+            ])
+        };
+        // in textual scope thanks to `#[macro_use]`
+        assert_eq!(
+            macros_of(&trie.children["storage"]),
+            vec![make_path("log_event")]
+        );
+        // exported at the root of the crate
+        assert_eq!(
+            macros_of(&trie.children["configuration"]),
+            vec![make_path("check_config")]
+        );
+        // `println!` and `assert!` are not defined in the crate
+        assert!(macros_of(&trie.children["macros"]).is_empty());
     }
-    Ok(())
 }
@@ -3,17 +3,28 @@
  * This file is part of Cargo Graphmod.
  * SPDX-License-Identifier: GPL-3.0-only
  */
-use std::collections::BTreeSet as Set;
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    path::Path,
+};
 
 use crate::{
-    dependencies::{DependencyPath, FilePath},
+    dependencies::{Dependency, EdgeKind, FilePath, Span},
     dependencies_graph::DependenciesGraph,
-    dependencies_processor::DependencyProcessor,
+    dependencies_processor::{DependencyProcessor, Target},
     formatter::{colors, Formatter},
+    package::Package,
 };
 
 const OUTPUT_SEPARATOR: &str = "::";
 const CLUSTER_SEPARATOR: &str = "___";
+const TEST_STYLE: &str = "style=\"dashed\"";
+const GLOB_STYLE: &str = "arrowhead=\"empty\"";
+const MACRO_STYLE: &str = "color=\"#1f78b4\"";
+const SIGNATURE_STYLE: &str = "color=\"#e31a1c\",label=\"signature\"";
+const IMPLEMENTS_STYLE: &str = "color=\"#33a02c\",label=\"implements\"";
+const DERIVES_STYLE: &str = "color=\"#ff7f00\",label=\"derives\"";
+const ATTRIBUTE_STYLE: &str = "color=\"#6a3d9a\",label=\"attribute\"";
 
 fn cluster_id(path: &str) -> String {
     path.split(OUTPUT_SEPARATOR)
@@ -21,6 +32,26 @@ fn cluster_id(path: &str) -> String {
         .join(CLUSTER_SEPARATOR)
 }
 
+fn show_vertex(
+    path: &str,
+    dirname: &str,
+    basename: &str,
+    file: Option<&Path>,
+    indentation: &str,
+) -> String {
+    let tooltip = file
+        .map(|file| format!(",tooltip=\"{}\"", file.display()))
+        .unwrap_or_default();
+    format!(
+        "{}\"{}\"[label=\"{}\",style=\"filled\",fillcolor=\"{}\"{}]\n",
+        indentation,
+        path,
+        basename,
+        colors::make_random_color(dirname),
+        tooltip
+    )
+}
+
 fn show_vertices(trie: &DependenciesGraph, dirname: &str, basename: &str, level: usize) -> String {
     let path = if basename.is_empty() {
         String::new()
@@ -28,19 +59,44 @@ fn show_vertices(trie: &DependenciesGraph, dirname: &str, basename: &str, level:
         String::from(dirname) + OUTPUT_SEPARATOR + basename
     };
     let indentation = "  ".repeat(level);
-    if trie.children.is_empty() {
-        format!(
-            "{}\"{}\"[label=\"{}\",style=\"filled\",fillcolor=\"{}\"]\n",
-            indentation,
-            path,
-            basename,
-            colors::make_random_color(dirname)
-        )
+    let file = trie
+        .value
+        .as_ref()
+        .and_then(|module| module.file.as_deref());
+    let items = trie
+        .value
+        .iter()
+        .flat_map(|module| module.items.keys())
+        .collect::<Vec<_>>();
+    if trie.children.is_empty() && items.is_empty() {
+        show_vertex(&path, dirname, basename, file, &indentation)
     } else {
+        // a module with inline submodules or items is drawn inside the cluster of its contents
+        let own_vertex = match trie.value {
+            Some(_) if !basename.is_empty() => {
+                show_vertex(&path, dirname, basename, file, &"  ".repeat(level + 1))
+            }
+            _ => String::new(),
+        };
+        let item_vertices = items
+            .into_iter()
+            .map(|item| {
+                show_vertex(
+                    &(path.clone() + OUTPUT_SEPARATOR + item),
+                    &path,
+                    item,
+                    None,
+                    &"  ".repeat(level + 1),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("");
         format!("{}subgraph cluster_{} {{\n", indentation, cluster_id(&path))
             + &format!("{}label=\"{}\"\n", indentation, basename)
             + &format!("{}color=\"{}\"\n", indentation, colors::make_gray(level))
             + &format!("{}style=\"filled\"\n", indentation)
+            + &own_vertex
+            + &item_vertices
             + &trie
                 .children
                 .iter()
@@ -55,23 +111,100 @@ fn make_vertex(path: &FilePath) -> String {
     OUTPUT_SEPARATOR.to_owned() + &path.0.join(OUTPUT_SEPARATOR)
 }
 
-fn make_arrow<Processor: DependencyProcessor>(
+fn make_target_vertex(target: &Target) -> String {
+    match &target.item {
+        Some(item) => make_vertex(&target.path) + OUTPUT_SEPARATOR + item,
+        None => make_vertex(&target.path),
+    }
+}
+
+/**
+ * The arrows from `source`, which is either the module at `current_path` or one of its items.
+ */
+fn make_arrows<Processor: DependencyProcessor>(
     trie: &DependenciesGraph,
+    source: &str,
     current_path: &FilePath,
-    dependency: &DependencyPath,
-    pkg_name: &str,
-) -> Option<String> {
-    let target = Processor::compute_target(trie, current_path, dependency, pkg_name);
-    if target.0.is_empty() {
-        None
-    } else {
-        Some(
-            String::from("\"")
-                + &make_vertex(current_path)
-                + "\" -> \""
-                + &make_vertex(&target)
-                + "\"",
-        )
+    dependency: &Dependency,
+    package: &Package,
+) -> Vec<(String, Target)> {
+    Processor::compute_targets(trie, current_path, dependency, package)
+        .into_iter()
+        .filter(|target| !target.path.0.is_empty())
+        .map(|target| {
+            (
+                String::from("\"") + source + "\" -> \"" + &make_target_vertex(&target) + "\"",
+                target,
+            )
+        })
+        .collect()
+}
+
+/**
+ * What is known about the dependencies drawn by a single arrow.
+ */
+struct ArrowAttributes {
+    /** An arrow is test-only when all the dependencies behind it are. */
+    test_only: bool,
+    /** An arrow is a glob import when any dependency behind it is. */
+    glob: bool,
+    aliases: Set<String>,
+    /** The facade modules gone through by following re-exports. */
+    facades: Set<String>,
+    /** Where the dependencies come from in the sources, if known. */
+    spans: Set<Span>,
+}
+
+impl Default for ArrowAttributes {
+    fn default() -> Self {
+        ArrowAttributes {
+            test_only: true,
+            glob: false,
+            aliases: Set::new(),
+            facades: Set::new(),
+            spans: Set::new(),
+        }
+    }
+}
+
+impl ArrowAttributes {
+    fn show(&self, kind: EdgeKind) -> String {
+        let mut attributes = vec![];
+        match kind {
+            EdgeKind::Use => {}
+            EdgeKind::Signature => attributes.push(String::from(SIGNATURE_STYLE)),
+            EdgeKind::Macro => attributes.push(String::from(MACRO_STYLE)),
+            EdgeKind::Implements => attributes.push(String::from(IMPLEMENTS_STYLE)),
+            EdgeKind::Derives => attributes.push(String::from(DERIVES_STYLE)),
+            EdgeKind::Attribute => attributes.push(String::from(ATTRIBUTE_STYLE)),
+        }
+        if self.test_only {
+            attributes.push(String::from(TEST_STYLE));
+        }
+        if self.glob {
+            attributes.push(String::from(GLOB_STYLE));
+        }
+        let mut tooltip = vec![];
+        if !self.aliases.is_empty() {
+            let aliases = self.aliases.iter().cloned().collect::<Vec<_>>();
+            tooltip.push(format!("as {}", aliases.join(", ")));
+        }
+        if !self.facades.is_empty() {
+            let facades = self.facades.iter().cloned().collect::<Vec<_>>();
+            tooltip.push(format!("via {}", facades.join(", ")));
+        }
+        if !self.spans.is_empty() {
+            let spans = self.spans.iter().map(Span::to_string).collect::<Vec<_>>();
+            tooltip.push(format!("at {}", spans.join(", ")));
+        }
+        if !tooltip.is_empty() {
+            attributes.push(format!("tooltip=\"{}\"", tooltip.join("; ")));
+        }
+        if attributes.is_empty() {
+            String::new()
+        } else {
+            format!("[{}]", attributes.join(","))
+        }
     }
 }
 
@@ -79,16 +212,39 @@ fn show_dependencies_from_vertex<Processor: DependencyProcessor>(
     current_trie: &DependenciesGraph,
     whole_trie: &DependenciesGraph,
     current_path: &FilePath,
-    pkg_name: &str,
+    package: &Package,
 ) -> Option<String> {
-    current_trie.value.as_ref().map(|dependencies| {
-        dependencies
+    current_trie.value.as_ref().map(|module| {
+        // dependencies of different kinds are drawn as distinct arrows
+        let mut arrows = Map::<(String, EdgeKind), ArrowAttributes>::new();
+        let module_vertex = make_vertex(current_path);
+        let sources = module
+            .dependencies
             .iter()
-            .filter_map(|dependency| {
-                make_arrow::<Processor>(whole_trie, current_path, dependency, pkg_name)
-            })
-            .collect::<Set<_>>()
+            .map(|dependency| (module_vertex.clone(), dependency))
+            .chain(module.items.iter().flat_map(|(item, dependencies)| {
+                let item_vertex = module_vertex.clone() + OUTPUT_SEPARATOR + item;
+                dependencies
+                    .iter()
+                    .map(move |dependency| (item_vertex.clone(), dependency))
+            }));
+        for (source, dependency) in sources {
+            for (arrow, target) in
+                make_arrows::<Processor>(whole_trie, &source, current_path, dependency, package)
+            {
+                let attributes = arrows.entry((arrow, dependency.kind)).or_default();
+                attributes.test_only &= dependency.test_only;
+                attributes.glob |= dependency.glob;
+                attributes.aliases.extend(target.alias);
+                attributes
+                    .facades
+                    .extend(target.via.iter().map(make_vertex));
+                attributes.spans.extend(dependency.span.clone());
+            }
+        }
+        arrows
             .into_iter()
+            .map(|((arrow, kind), attributes)| arrow + &attributes.show(kind))
             .collect::<Vec<_>>()
             .join("\n")
     })
@@ -98,13 +254,13 @@ fn show_arcs<Processor: DependencyProcessor>(
     current_trie: &DependenciesGraph,
     whole_trie: &DependenciesGraph,
     FilePath(path): &FilePath,
-    pkg_name: &str,
+    package: &Package,
 ) -> String {
     show_dependencies_from_vertex::<Processor>(
         current_trie,
         whole_trie,
         &FilePath(path.clone()),
-        pkg_name,
+        package,
     )
     .unwrap_or_default()
         + &current_trie
@@ -113,7 +269,7 @@ fn show_arcs<Processor: DependencyProcessor>(
             .map(|(name, child)| {
                 let mut new_path = path.clone();
                 new_path.push(name.clone());
-                show_arcs::<Processor>(child, whole_trie, &FilePath(new_path), pkg_name)
+                show_arcs::<Processor>(child, whole_trie, &FilePath(new_path), package)
             })
             .filter(|s| !s.is_empty())
             .collect::<Vec<_>>()
@@ -123,23 +279,27 @@ fn show_arcs<Processor: DependencyProcessor>(
 pub struct DotFormatter {}
 
 impl Formatter for DotFormatter {
-    fn show<Processor: DependencyProcessor>(trie: &DependenciesGraph, pkg_name: &str) -> String {
+    fn show<Processor: DependencyProcessor>(trie: &DependenciesGraph, package: &Package) -> String {
         String::from("digraph dependencies {\n")
             + &show_vertices(trie, "", "", 1)
-            + &show_arcs::<Processor>(trie, trie, &FilePath(vec![]), pkg_name)
+            + &show_arcs::<Processor>(trie, trie, &FilePath(vec![]), package)
             + "\n}\n"
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap as Map;
+    use std::{
+        collections::{BTreeMap as Map, VecDeque},
+        path::PathBuf,
+    };
 
     use crate::{
-        dependencies::DependencyPath,
-        dependencies_graph::DependenciesGraph,
+        dependencies::{Dependency, DependencyPath, EdgeKind, Span},
+        dependencies_graph::{DependenciesGraph, Module},
         dependencies_processor::rust_processor::target_computer::RustDependencyProcessor,
         formatter::{dot_formatter::DotFormatter, Formatter},
+        package::Package,
     };
 
     fn make_trie() -> DependenciesGraph {
@@ -161,23 +321,33 @@ mod tests {
                             (
                                 String::from("bar"),
                                 DependenciesGraph {
-                                    value: Some(vec![
-                                        DependencyPath(vec![
-                                            String::from("crate"),
-                                            String::from("abc"),
-                                        ]),
-                                        DependencyPath(vec![String::from("std")]),
-                                    ]),
+                                    value: Some(Module {
+                                        dependencies: vec![
+                                            DependencyPath(vec![
+                                                String::from("crate"),
+                                                String::from("abc"),
+                                            ])
+                                            .into(),
+                                            DependencyPath(vec![String::from("std")]).into(),
+                                        ],
+                                        file: None,
+                                        items: Map::new(),
+                                    }),
                                     children: Map::new(),
                                 },
                             ),
                             (
                                 String::from("mod"),
                                 DependenciesGraph {
-                                    value: Some(vec![DependencyPath(vec![
-                                        String::from("bar"),
-                                        String::from("baz"),
-                                    ])]),
+                                    value: Some(Module {
+                                        dependencies: vec![DependencyPath(vec![
+                                            String::from("bar"),
+                                            String::from("baz"),
+                                        ])
+                                        .into()],
+                                        file: None,
+                                        items: Map::new(),
+                                    }),
                                     children: Map::new(),
                                 },
                             ),
@@ -187,26 +357,37 @@ mod tests {
                 (
                     String::from("abc"),
                     DependenciesGraph {
-                        value: Some(vec![
-                            DependencyPath(vec![
-                                String::from("crate"),
-                                String::from("foo"),
-                                String::from("Panel"),
-                            ]),
-                            DependencyPath(vec![String::from("crate"), String::from("Widget")]),
-                        ]),
+                        value: Some(Module {
+                            dependencies: vec![
+                                DependencyPath(vec![
+                                    String::from("crate"),
+                                    String::from("foo"),
+                                    String::from("Panel"),
+                                ])
+                                .into(),
+                                DependencyPath(vec![String::from("crate"), String::from("Widget")])
+                                    .into(),
+                            ],
+                            file: None,
+                            items: Map::new(),
+                        }),
                         children: Map::new(),
                     },
                 ),
                 (
                     String::from("def"),
                     DependenciesGraph {
-                        value: Some(vec![DependencyPath(vec![
-                            String::from("crate"),
-                            String::from("foo"),
-                            String::from("bar"),
-                            String::from("Widget"),
-                        ])]),
+                        value: Some(Module {
+                            dependencies: vec![DependencyPath(vec![
+                                String::from("crate"),
+                                String::from("foo"),
+                                String::from("bar"),
+                                String::from("Widget"),
+                            ])
+                            .into()],
+                            file: None,
+                            items: Map::new(),
+                        }),
                         children: Map::new(),
                     },
                 ),
@@ -217,7 +398,8 @@ mod tests {
     #[test]
     fn it_outputs_to_dot() {
         let trie = make_trie();
-        let result = DotFormatter::show::<RustDependencyProcessor>(&trie, "my_crate");
+        let result =
+            DotFormatter::show::<RustDependencyProcessor>(&trie, &Package::new("my_crate"));
         let expected = String::from(
             r##"digraph dependencies {
   subgraph cluster_ {
@@ -246,4 +428,191 @@ mod tests {
         );
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn it_styles_the_arrows_and_the_nodes() {
+        let dependency = |path: &[&str]| -> Dependency {
+            DependencyPath(path.iter().map(|segment| segment.to_string()).collect()).into()
+        };
+        let cases = [
+            (
+                "plain",
+                vec![dependency(&["crate", "mock"])],
+                None,
+                r#""::plain" -> "::mock""#,
+            ),
+            (
+                "tested",
+                vec![Dependency {
+                    test_only: true,
+                    ..dependency(&["crate", "mock"])
+                }],
+                None,
+                r#""::tested" -> "::mock"[style="dashed"]"#,
+            ),
+            (
+                "imp",
+                vec![],
+                Some("src/sys/unix.rs"),
+                r##""::imp"[label="imp",style="filled",fillcolor="#e3f38b",tooltip="src/sys/unix.rs"]"##,
+            ),
+            (
+                "aliased",
+                vec![dependency(&["json", "Value"])],
+                None,
+                r#""::aliased" -> "::serde_json"[tooltip="as json"]"#,
+            ),
+            (
+                "globbed",
+                vec![dependency(&["crate", "foo", "*"])],
+                None,
+                r#""::globbed" -> "::foo::mod"[arrowhead="empty"]"#,
+            ),
+            (
+                "logging",
+                vec![Dependency {
+                    kind: EdgeKind::Macro,
+                    ..dependency(&["crate", "macros", "log_event"])
+                }],
+                None,
+                r##""::logging" -> "::macros"[color="#1f78b4"]"##,
+            ),
+        ];
+        let mut trie = DependenciesGraph::new();
+        for name in ["lib", "mock", "macros"] {
+            trie.insert(VecDeque::from([String::from(name)]), Module::default());
+        }
+        trie.insert(
+            VecDeque::from([String::from("foo"), String::from("mod")]),
+            Module::default(),
+        );
+        for (name, dependencies, file, _) in &cases {
+            trie.insert(
+                VecDeque::from([String::from(*name)]),
+                Module {
+                    dependencies: dependencies.clone(),
+                    file: file.map(PathBuf::from),
+                    items: Map::new(),
+                },
+            );
+        }
+        let mut package = Package::new("my_crate");
+        package
+            .aliases
+            .insert(String::from("json"), String::from("serde_json"));
+        let result = DotFormatter::show::<RustDependencyProcessor>(&trie, &package);
+        for (_, _, _, expected) in cases {
+            assert!(result.contains(&format!("{}\n", expected)), "{}", expected);
+        }
+    }
+
+    #[test]
+    fn it_draws_inline_modules_inside_the_cluster_of_their_parent() {
+        let mut trie = DependenciesGraph::new();
+        trie.insert(VecDeque::from([String::from("lib")]), Module::default());
+        trie.insert(VecDeque::from([String::from("abc")]), Module::default());
+        trie.insert(
+            VecDeque::from([String::from("abc"), String::from("tests")]),
+            Module {
+                dependencies: vec![DependencyPath(vec![
+                    String::from("super"),
+                    String::from("Widget"),
+                ])
+                .into()],
+                file: None,
+                items: Map::new(),
+            },
+        );
+        let result =
+            DotFormatter::show::<RustDependencyProcessor>(&trie, &Package::new("my_crate"));
+        let expected = String::from(
+            r##"digraph dependencies {
+  subgraph cluster_ {
+  label=""
+  color="#eeeeee"
+  style="filled"
+    subgraph cluster____abc {
+    label="abc"
+    color="#dddddd"
+    style="filled"
+      "::abc"[label="abc",style="filled",fillcolor="#e3f38b"]
+      "::abc::tests"[label="tests",style="filled",fillcolor="#bc90ce"]
+    }
+    "::lib"[label="lib",style="filled",fillcolor="#e3f38b"]
+  }
+"::abc::tests" -> "::abc"
+}
+"##,
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn it_lists_the_spans_behind_an_arrow() {
+        let span = |line, column| {
+            Some(Span {
+                file: PathBuf::from("src/abc.rs"),
+                line,
+                column,
+            })
+        };
+        let mut trie = DependenciesGraph::new();
+        trie.insert(VecDeque::from([String::from("lib")]), Module::default());
+        trie.insert(
+            VecDeque::from([String::from("abc")]),
+            Module {
+                dependencies: vec![
+                    Dependency {
+                        span: span(4, 5),
+                        ..DependencyPath(vec![String::from("crate"), String::from("Widget")]).into()
+                    },
+                    Dependency {
+                        span: span(1, 1),
+                        ..DependencyPath(vec![String::from("crate"), String::from("Panel")]).into()
+                    },
+                ],
+                file: None,
+                items: Map::new(),
+            },
+        );
+        let result =
+            DotFormatter::show::<RustDependencyProcessor>(&trie, &Package::new("my_crate"));
+        assert!(result
+            .contains("\"::abc\" -> \"::lib\"[tooltip=\"at src/abc.rs:1:1, src/abc.rs:4:5\"]\n"));
+    }
+
+    #[test]
+    fn it_labels_the_arrows_with_their_kind() {
+        let dependency = |name: &str, kind| Dependency {
+            kind,
+            ..DependencyPath(vec![String::from("crate"), String::from(name)]).into()
+        };
+        let mut trie = DependenciesGraph::new();
+        for name in ["lib", "ports", "macros", "time", "domain"] {
+            trie.insert(VecDeque::from([String::from(name)]), Module::default());
+        }
+        trie.insert(
+            VecDeque::from([String::from("db")]),
+            Module {
+                dependencies: vec![
+                    dependency("ports", EdgeKind::Implements),
+                    dependency("macros", EdgeKind::Derives),
+                    dependency("time", EdgeKind::Attribute),
+                    dependency("domain", EdgeKind::Signature),
+                ],
+                file: None,
+                items: Map::new(),
+            },
+        );
+        let result =
+            DotFormatter::show::<RustDependencyProcessor>(&trie, &Package::new("my_crate"));
+        assert!(
+            result.contains("\"::db\" -> \"::ports\"[color=\"#33a02c\",label=\"implements\"]\n")
+        );
+        assert!(result.contains("\"::db\" -> \"::macros\"[color=\"#ff7f00\",label=\"derives\"]\n"));
+        assert!(result.contains("\"::db\" -> \"::time\"[color=\"#6a3d9a\",label=\"attribute\"]\n"));
+        assert!(
+            result.contains("\"::db\" -> \"::domain\"[color=\"#e31a1c\",label=\"signature\"]\n")
+        );
+    }
 }
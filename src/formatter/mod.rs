@@ -1,8 +1,11 @@
-use crate::{dependencies_graph::DependenciesGraph, dependencies_processor::DependencyProcessor};
+use crate::{
+    dependencies_graph::DependenciesGraph, dependencies_processor::DependencyProcessor,
+    package::Package,
+};
 
 mod colors;
 pub mod dot_formatter;
 
 pub trait Formatter {
-    fn show<Processor: DependencyProcessor>(trie: &DependenciesGraph, pkg_name: &str) -> String;
+    fn show<Processor: DependencyProcessor>(trie: &DependenciesGraph, package: &Package) -> String;
 }
@@ -6,10 +6,17 @@
 
 #[doc = include_str!("../README.md")]
 pub mod app_builder;
+mod cfg;
+pub mod configuration;
+mod crate_targets;
 mod dependencies;
 mod dependencies_graph;
 mod dependencies_processor;
 mod files_reader;
 mod formatter;
+mod manifest;
+mod metadata;
+mod package;
 mod parser;
+mod targets;
 mod trie;
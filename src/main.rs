@@ -3,19 +3,104 @@
  * This file is part of Cargo Graphmod.
  * SPDX-License-Identifier: GPL-3.0-only
  */
-use std::{env, path::Path};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process,
+};
 
-use cargo_graphmod::app_builder::run_app;
+use cargo_graphmod::{
+    app_builder::{run_app, run_workspace},
+    configuration::{Configuration, MetadataSource},
+};
 
 const GRAPHMOD: &str = "graphmod";
 const SRC: &str = "src";
+const OPTION_PREFIX: &str = "--";
+const PARSER_OPTION: &str = "--parser=";
+const GRANULARITY_OPTION: &str = "--granularity=";
+const TESTS_OPTION: &str = "--tests";
+const FOLLOW_REEXPORTS_OPTION: &str = "--follow-reexports";
+const SPANS_OPTION: &str = "--spans";
+const WORKSPACE_OPTION: &str = "--workspace";
+const TARGETS_OPTION: &str = "--targets=";
+const METADATA_OPTION: &str = "--metadata";
+const METADATA_FILE_OPTION: &str = "--metadata=";
+const FEATURES_OPTION: &str = "--features=";
+const ALL_FEATURES_OPTION: &str = "--all-features";
+const NO_DEFAULT_FEATURES_OPTION: &str = "--no-default-features";
+const TARGET_OPTION: &str = "--target=";
+const CFG_OPTION: &str = "--cfg=";
 
 fn basename(path: &Path) -> String {
     path.file_name().unwrap().to_str().unwrap().to_string()
 }
 
+fn parse_options(options: &[String]) -> Result<Configuration, String> {
+    let mut configuration = Configuration::default();
+    for option in options {
+        if let Some(parser) = option.strip_prefix(PARSER_OPTION) {
+            configuration.parser = parser.parse()?;
+        } else if let Some(granularity) = option.strip_prefix(GRANULARITY_OPTION) {
+            configuration.granularity = granularity.parse()?;
+        } else if option == TESTS_OPTION {
+            configuration.tests = true;
+        } else if option == FOLLOW_REEXPORTS_OPTION {
+            configuration.follow_reexports = true;
+        } else if option == SPANS_OPTION {
+            configuration.spans = true;
+        } else if option == WORKSPACE_OPTION {
+            configuration.workspace = true;
+        } else if let Some(targets) = option.strip_prefix(TARGETS_OPTION) {
+            configuration.targets = Some(targets.parse()?);
+        } else if option == METADATA_OPTION {
+            configuration.metadata = Some(MetadataSource::Cargo);
+        } else if let Some(path) = option.strip_prefix(METADATA_FILE_OPTION) {
+            configuration.metadata = Some(MetadataSource::File(PathBuf::from(path)));
+        } else if let Some(features) = option.strip_prefix(FEATURES_OPTION) {
+            let selection = configuration.features.get_or_insert_with(Default::default);
+            selection.features.extend(
+                features
+                    .split([',', ' '])
+                    .filter(|feature| !feature.is_empty())
+                    .map(String::from),
+            );
+        } else if option == ALL_FEATURES_OPTION {
+            configuration
+                .features
+                .get_or_insert_with(Default::default)
+                .all_features = true;
+        } else if option == NO_DEFAULT_FEATURES_OPTION {
+            configuration
+                .features
+                .get_or_insert_with(Default::default)
+                .no_default_features = true;
+        } else if let Some(target) = option.strip_prefix(TARGET_OPTION) {
+            configuration.platform.set_target(target)?;
+        } else if let Some(cfg) = option.strip_prefix(CFG_OPTION) {
+            configuration.platform.add_cfg(cfg)?;
+        } else {
+            return Err(format!("Unknown option: {}", option));
+        }
+    }
+    Ok(configuration)
+}
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let (options, args): (Vec<String>, Vec<String>) =
+        env::args().partition(|arg| arg.starts_with(OPTION_PREFIX));
+    let configuration = parse_options(&options).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(1);
+    });
+    if configuration.workspace {
+        let root = match args.get(1) {
+            Some(dir) if dir != GRAPHMOD => dir.as_str(),
+            _ => ".",
+        };
+        println!("{}", run_workspace(root, &configuration));
+        return;
+    }
     let (directory, pkg_name) = match args.get(1) {
         Some(dir) if dir != GRAPHMOD => (dir.to_string() + "/" + SRC, basename(Path::new(dir))),
         _ => (
@@ -27,6 +112,6 @@ fn main() {
         ),
     };
     let pkg_rust_name = pkg_name.replace('-', "_");
-    let output = run_app(&directory, &pkg_rust_name);
+    let output = run_app(&directory, &pkg_rust_name, &configuration);
     println!("{}", output);
 }
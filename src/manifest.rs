@@ -0,0 +1,570 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    fmt,
+    fs::{read_dir, read_to_string},
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+use crate::{
+    configuration::{FeatureSelection, TargetKind},
+    package::Edition,
+};
+
+pub const MANIFEST: &str = "Cargo.toml";
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+const TARGET: &str = "target";
+const PACKAGE: &str = "package";
+const LIB: &str = "lib";
+const NAME: &str = "name";
+const PATH: &str = "path";
+/** The targets of each kind are listed by `[lib]`, `[[bin]]`, `[[example]]`, `[[test]]` and `[[bench]]`. */
+const TARGET_KINDS: [TargetKind; 5] = [
+    TargetKind::Lib,
+    TargetKind::Bin,
+    TargetKind::Example,
+    TargetKind::Test,
+    TargetKind::Bench,
+];
+const EDITION: &str = "edition";
+const WORKSPACE: &str = "workspace";
+const MEMBERS: &str = "members";
+const EXCLUDE: &str = "exclude";
+const WILDCARD: char = '*';
+const FEATURES: &str = "features";
+const DEFAULT: &str = "default";
+const OPTIONAL: &str = "optional";
+const DEPENDENCY_PREFIX: &str = "dep:";
+const FEATURE_SEPARATOR: char = '/';
+const WEAK_MARKER: char = '?';
+
+/**
+ * The crate name of a package, as it appears in paths.
+ */
+fn crate_name(package: &str) -> String {
+    package.replace('-', "_")
+}
+
+/**
+ * Whether a directory name matches a segment of a `[workspace] members` pattern.
+ */
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.split_once(WILDCARD) {
+        None => pattern == name,
+        Some((prefix, rest)) => name.strip_prefix(prefix).is_some_and(|name| {
+            name.char_indices()
+                .map(|(index, _)| index)
+                .chain([name.len()])
+                .any(|index| matches_pattern(rest, &name[index..]))
+        }),
+    }
+}
+
+fn parse_edition(edition: &str) -> Edition {
+    edition.parse().unwrap_or_else(|error| {
+        eprintln!("Warning: {} It has been ignored.", error);
+        Edition::default()
+    })
+}
+
+/**
+ * A target listed in the manifest, whose root may be given by `path`.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct TargetEntry {
+    pub kind: TargetKind,
+    pub name: Option<String>,
+    pub path: Option<PathBuf>,
+}
+
+/**
+ * The entry as written in the manifest, such as `[[bin]] name = "tool", path = "tools/tool.rs"`.
+ */
+impl fmt::Display for TargetEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            TargetKind::Lib => write!(f, "[{}]", self.kind)?,
+            _ => write!(f, "[[{}]]", self.kind)?,
+        }
+        let name = self
+            .name
+            .iter()
+            .map(|name| format!("{} = {:?}", NAME, name));
+        let path = self
+            .path
+            .iter()
+            .map(|path| format!("{} = {:?}", PATH, path.display().to_string()));
+        let settings = name.chain(path).collect::<Vec<_>>();
+        if !settings.is_empty() {
+            write!(f, " {}", settings.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * The `Cargo.toml` file of a package or a workspace.
+ */
+pub struct Manifest {
+    table: Table,
+}
+
+impl Manifest {
+    /**
+     * A missing manifest is not an error, as the sources may be analyzed on their own.
+     */
+    pub fn read(path: &Path) -> Option<Self> {
+        let contents = read_to_string(path).ok()?;
+        match contents.parse::<Table>() {
+            Ok(table) => Some(Manifest { table }),
+            Err(error) => {
+                eprintln!(
+                    "Warning: unable to parse {} ({}); it has been ignored.",
+                    path.display(),
+                    error.message()
+                );
+                None
+            }
+        }
+    }
+
+    /**
+     * The name the library is imported by: the `[lib] name` if given, else the package name as an identifier.
+     */
+    pub fn library_name(&self) -> Option<String> {
+        let name = self.table.get(LIB).and_then(|lib| lib.get(NAME)?.as_str());
+        name.map(crate_name).or_else(|| self.package_name())
+    }
+
+    /**
+     * The crate name of the package, which its main binary is named after.
+     */
+    pub fn package_name(&self) -> Option<String> {
+        self.table.get(PACKAGE)?.get(NAME)?.as_str().map(crate_name)
+    }
+
+    /**
+     * The manifest of the workspace the package in `directory` belongs to, if any,
+     * found in the nearest directory up from it whose manifest has a `[workspace]` table, as Cargo looks for it.
+     */
+    pub fn enclosing_workspace(directory: &Path) -> Option<Self> {
+        let directory = directory.canonicalize().ok()?;
+        directory
+            .ancestors()
+            .map(|ancestor| ancestor.join(MANIFEST))
+            .filter(|path| path.is_file())
+            .filter_map(|path| Manifest::read(&path))
+            .find(|manifest| manifest.table.contains_key(WORKSPACE))
+    }
+
+    /**
+     * The targets listed by `[lib]`, `[[bin]]`, `[[example]]`, `[[test]]` and `[[bench]]`.
+     */
+    pub fn declared_targets(&self) -> Vec<TargetEntry> {
+        TARGET_KINDS
+            .into_iter()
+            .flat_map(|kind| {
+                let entries = match self.table.get(&kind.to_string()) {
+                    Some(Value::Table(entry)) => vec![entry],
+                    Some(Value::Array(entries)) => {
+                        entries.iter().filter_map(Value::as_table).collect()
+                    }
+                    _ => vec![],
+                };
+                entries.into_iter().map(move |entry| TargetEntry {
+                    kind,
+                    name: entry.get(NAME).and_then(Value::as_str).map(String::from),
+                    path: entry.get(PATH).and_then(Value::as_str).map(PathBuf::from),
+                })
+            })
+            .collect()
+    }
+
+    /**
+     * Whether Cargo looks for the targets of that kind which are not listed,
+     * unless told otherwise by `autolib`, `autobins`, `autoexamples`, `autotests` or `autobenches`.
+     */
+    pub fn discovers(&self, kind: TargetKind) -> bool {
+        let key = match kind {
+            TargetKind::Lib => "autolib",
+            TargetKind::Bin => "autobins",
+            TargetKind::Example => "autoexamples",
+            TargetKind::Test => "autotests",
+            TargetKind::Bench => "autobenches",
+        };
+        self.table
+            .get(PACKAGE)
+            .and_then(|package| package.get(key))
+            .and_then(Value::as_bool)
+            != Some(false)
+    }
+
+    /**
+     * Cargo assumes the 2015 edition when none is given.
+     * An edition inherited from the workspace (`edition.workspace = true`) is read from its manifest, if given;
+     * otherwise the default edition (2021) is assumed.
+     */
+    pub fn edition(&self, workspace: Option<&Manifest>) -> Edition {
+        let edition = self
+            .table
+            .get(PACKAGE)
+            .and_then(|package| package.get(EDITION));
+        match edition {
+            None => Edition::E2015,
+            Some(Value::String(edition)) => parse_edition(edition),
+            Some(_) => workspace
+                .and_then(|workspace| {
+                    workspace
+                        .table
+                        .get(WORKSPACE)?
+                        .get(PACKAGE)?
+                        .get(EDITION)?
+                        .as_str()
+                })
+                .map(parse_edition)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn workspace_patterns(&self, key: &str) -> Vec<&str> {
+        self.table
+            .get(WORKSPACE)
+            .and_then(|workspace| workspace.get(key))
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect()
+    }
+
+    /**
+     * The directories of the members of a workspace rooted at `root`, including the root package if any.
+     * The patterns of `[workspace] members` may contain `*` wildcards.
+     */
+    pub fn workspace_members(&self, root: &Path) -> Vec<PathBuf> {
+        let excluded = self
+            .workspace_patterns(EXCLUDE)
+            .into_iter()
+            .map(|path| root.join(path))
+            .collect::<Set<_>>();
+        let mut members = Set::new();
+        if self.table.contains_key(PACKAGE) {
+            members.insert(root.to_path_buf());
+        }
+        for pattern in self.workspace_patterns(MEMBERS) {
+            let mut directories = vec![root.to_path_buf()];
+            for segment in pattern.split('/').filter(|segment| !segment.is_empty()) {
+                directories = directories
+                    .into_iter()
+                    .flat_map(|directory| {
+                        if !segment.contains(WILDCARD) {
+                            return vec![directory.join(segment)];
+                        }
+                        read_dir(&directory)
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|entry| entry.ok())
+                            .filter(|entry| {
+                                entry
+                                    .file_name()
+                                    .to_str()
+                                    .is_some_and(|name| matches_pattern(segment, name))
+                            })
+                            .map(|entry| entry.path())
+                            .collect()
+                    })
+                    .collect();
+            }
+            members.extend(
+                directories
+                    .into_iter()
+                    .filter(|directory| directory.join(MANIFEST).is_file()),
+            );
+        }
+        members
+            .into_iter()
+            .filter(|member| !excluded.contains(member))
+            .collect()
+    }
+
+    fn dependency_tables(&self) -> Vec<&Table> {
+        let platform_tables = self
+            .table
+            .get(TARGET)
+            .and_then(Value::as_table)
+            .into_iter()
+            .flat_map(|targets| targets.values())
+            .filter_map(Value::as_table);
+        [&self.table]
+            .into_iter()
+            .chain(platform_tables)
+            .flat_map(|table| DEPENDENCY_TABLES.iter().filter_map(|name| table.get(*name)))
+            .filter_map(Value::as_table)
+            .collect()
+    }
+
+    /**
+     * `foo = { package = "bar" }` makes the crate `bar` known as `foo`.
+     */
+    pub fn renamed_dependencies(&self) -> Map<String, String> {
+        self.dependency_tables()
+            .into_iter()
+            .flat_map(|table| table.iter())
+            .filter_map(|(name, specification)| {
+                let package = specification.get(PACKAGE)?.as_str()?;
+                Some((crate_name(name), crate_name(package)))
+            })
+            .collect()
+    }
+
+    /**
+     * Optional dependencies define implicit features named after them,
+     * unless they are referred to as `dep:foo` by a feature.
+     */
+    fn optional_dependencies(&self, features: Option<&Table>) -> Set<String> {
+        let explicit = features
+            .into_iter()
+            .flat_map(|table| table.values())
+            .filter_map(Value::as_array)
+            .flatten()
+            .filter_map(|requirement| requirement.as_str()?.strip_prefix(DEPENDENCY_PREFIX))
+            .collect::<Set<_>>();
+        self.dependency_tables()
+            .into_iter()
+            .flat_map(|table| table.iter())
+            .filter(|(_, specification)| {
+                specification.get(OPTIONAL).and_then(Value::as_bool) == Some(true)
+            })
+            .map(|(name, _)| name.clone())
+            .filter(|name| !explicit.contains(name.as_str()))
+            .collect()
+    }
+
+    /**
+     * The selected features, along with the features they enable in turn, as Cargo resolves them.
+     */
+    pub fn enabled_features(&self, selection: &FeatureSelection) -> Set<String> {
+        let features = self.table.get(FEATURES).and_then(Value::as_table);
+        let optional_dependencies = self.optional_dependencies(features);
+        let mut pending = selection.features.clone();
+        if !selection.no_default_features {
+            pending.push(String::from(DEFAULT));
+        }
+        if selection.all_features {
+            pending.extend(features.into_iter().flat_map(|table| table.keys().cloned()));
+            pending.extend(optional_dependencies.iter().cloned());
+        }
+        let mut enabled = Set::new();
+        while let Some(feature) = pending.pop() {
+            let Some(requirements) = features
+                .and_then(|table| table.get(&feature))
+                .and_then(Value::as_array)
+            else {
+                enabled.insert(feature);
+                continue;
+            };
+            if !enabled.insert(feature) {
+                continue;
+            }
+            for requirement in requirements.iter().filter_map(Value::as_str) {
+                if requirement.starts_with(DEPENDENCY_PREFIX) {
+                    continue;
+                }
+                // `foo/bar` enables the optional dependency `foo`, unless written `foo?/bar`
+                match requirement.split_once(FEATURE_SEPARATOR) {
+                    Some((dependency, _)) if dependency.ends_with(WEAK_MARKER) => {}
+                    Some((dependency, _)) if optional_dependencies.contains(dependency) => {
+                        pending.push(String::from(dependency))
+                    }
+                    Some(_) => {}
+                    None => pending.push(String::from(requirement)),
+                }
+            }
+        }
+        enabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap as Map, BTreeSet as Set},
+        path::{Path, PathBuf},
+    };
+
+    use crate::{
+        configuration::{FeatureSelection, TargetKind},
+        package::Edition,
+    };
+
+    use super::{Manifest, TargetEntry, MEMBERS};
+
+    #[test]
+    fn it_reads_renamed_dependencies() {
+        let manifest = Manifest {
+            table: r#"
+[package]
+name = "my-crate"
+
+[dependencies]
+regex = "1"
+json = { package = "serde_json", version = "1" }
+
+[dev-dependencies.old-rand]
+package = "rand"
+version = "0.7"
+
+[target.'cfg(unix)'.dependencies]
+sys = { package = "nix-sys" }
+"#
+            .parse()
+            .unwrap(),
+        };
+        assert_eq!(
+            manifest.renamed_dependencies(),
+            Map::from([
+                (String::from("json"), String::from("serde_json")),
+                (String::from("old_rand"), String::from("rand")),
+                (String::from("sys"), String::from("nix_sys")),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_reads_the_library_name() {
+        let name = |table: &str| {
+            Manifest {
+                table: table.parse().unwrap(),
+            }
+            .library_name()
+        };
+        assert_eq!(
+            name("[package]\nname = \"my-crate\""),
+            Some(String::from("my_crate"))
+        );
+        assert_eq!(
+            name("[package]\nname = \"my-crate\"\n[lib]\nname = \"mine\""),
+            Some(String::from("mine"))
+        );
+        assert_eq!(name("[workspace]\nmembers = []"), None);
+    }
+
+    #[test]
+    fn it_reads_the_declared_targets() {
+        let manifest = Manifest {
+            table: r#"
+                [package]
+                name = "my-crate"
+                autobins = false
+
+                [lib]
+                path = "lib/core.rs"
+
+                [[bin]]
+                name = "tool"
+                path = "tools/tool.rs"
+
+                [[example]]
+                name = "demo"
+            "#
+            .parse()
+            .unwrap(),
+        };
+        let targets = manifest.declared_targets();
+        assert_eq!(
+            targets,
+            vec![
+                TargetEntry {
+                    kind: TargetKind::Lib,
+                    name: None,
+                    path: Some(PathBuf::from("lib/core.rs")),
+                },
+                TargetEntry {
+                    kind: TargetKind::Bin,
+                    name: Some(String::from("tool")),
+                    path: Some(PathBuf::from("tools/tool.rs")),
+                },
+                TargetEntry {
+                    kind: TargetKind::Example,
+                    name: Some(String::from("demo")),
+                    path: None,
+                },
+            ]
+        );
+        assert_eq!(targets[0].to_string(), r#"[lib] path = "lib/core.rs""#);
+        assert_eq!(
+            targets[1].to_string(),
+            r#"[[bin]] name = "tool", path = "tools/tool.rs""#
+        );
+        assert!(manifest.discovers(TargetKind::Lib));
+        assert!(!manifest.discovers(TargetKind::Bin));
+    }
+
+    #[test]
+    fn it_reads_the_edition() {
+        let manifest = |table: &str| Manifest {
+            table: table.parse().unwrap(),
+        };
+        let edition = |table: &str| manifest(table).edition(None);
+        assert_eq!(edition("[package]\nname = \"old\""), Edition::E2015);
+        assert_eq!(edition("[package]\nedition = \"2018\""), Edition::E2018);
+        assert_eq!(
+            edition("[package]\nedition.workspace = true"),
+            Edition::default()
+        );
+        let workspace = manifest("[workspace.package]\nedition = \"2015\"");
+        assert_eq!(
+            manifest("[package]\nedition.workspace = true").edition(Some(&workspace)),
+            Edition::E2015
+        );
+    }
+
+    #[test]
+    fn it_finds_the_enclosing_workspace() {
+        let workspace = Manifest::enclosing_workspace(Path::new("tests/workspace/crates/storage"))
+            .expect("the workspace manifest");
+        assert_eq!(workspace.workspace_patterns(MEMBERS), vec!["crates/*"]);
+    }
+
+    #[test]
+    fn it_resolves_enabled_features() {
+        let manifest = Manifest {
+            table: r#"
+[dependencies]
+serde = { version = "1", optional = true }
+yaml = { version = "0.9", optional = true }
+toml = { version = "0.8", optional = true }
+
+[features]
+default = ["json"]
+json = ["serde/derive"]
+config = ["dep:toml", "yaml?/std"]
+"#
+            .parse()
+            .unwrap(),
+        };
+        let features = |features: &[&str], all_features, no_default_features| {
+            manifest.enabled_features(&FeatureSelection {
+                features: features.iter().map(|s| s.to_string()).collect(),
+                all_features,
+                no_default_features,
+            })
+        };
+        let set = |features: &[&str]| features.iter().map(|s| s.to_string()).collect::<Set<_>>();
+        assert_eq!(
+            features(&[], false, false),
+            set(&["default", "json", "serde"])
+        );
+        assert_eq!(features(&["config"], false, true), set(&["config"]));
+        assert_eq!(
+            features(&[], true, true),
+            set(&["config", "default", "json", "serde", "yaml"])
+        );
+    }
+}
@@ -0,0 +1,217 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+use std::{
+    collections::BTreeSet as Set,
+    fs::read_to_string,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+    process::Command,
+};
+
+use serde_json::Value;
+
+const CARGO: &str = "cargo";
+const METADATA_ARGS: [&str; 4] = ["metadata", "--offline", "--format-version", "1"];
+const PACKAGES: &str = "packages";
+const WORKSPACE_MEMBERS: &str = "workspace_members";
+const RESOLVE: &str = "resolve";
+const NODES: &str = "nodes";
+const DEPS: &str = "deps";
+const DEP_KINDS: &str = "dep_kinds";
+const DEPENDENCIES: &str = "dependencies";
+const ID: &str = "id";
+const NAME: &str = "name";
+const RENAME: &str = "rename";
+const KIND: &str = "kind";
+const DEV: &str = "dev";
+
+/**
+ * Normal dependencies are available to every target, and dev-dependencies to the tests, examples and benches;
+ * build dependencies are only available to the build script, which is not drawn.
+ */
+fn is_available(kind: &Value, dev: bool) -> bool {
+    match kind.get(KIND).and_then(Value::as_str) {
+        None => true,
+        Some(DEV) => dev,
+        Some(_) => false,
+    }
+}
+
+/**
+ * The output of `cargo metadata`, which lists the dependencies of the packages.
+ */
+pub struct Metadata {
+    value: Value,
+}
+
+impl Metadata {
+    fn parse(contents: &str) -> Result<Self> {
+        serde_json::from_str(contents)
+            .map(|value| Metadata { value })
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+    }
+
+    /**
+     * Reads the output of `cargo metadata` saved to a file.
+     */
+    pub fn read(path: &Path) -> Result<Self> {
+        Self::parse(&read_to_string(path)?)
+    }
+
+    /**
+     * Runs `cargo metadata --offline` in `directory`, which needs the dependencies to have been fetched already.
+     */
+    pub fn run(directory: &Path) -> Result<Self> {
+        let mut command = Command::new(CARGO);
+        command.args(METADATA_ARGS);
+        // the current directory may be given as an empty path
+        if !directory.as_os_str().is_empty() {
+            command.current_dir(directory);
+        }
+        let output = command.output()?;
+        if !output.status.success() {
+            return Err(Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /**
+     * The package of the workspace whose crate name is `package_name`.
+     */
+    fn package(&self, package_name: &str) -> Option<&Value> {
+        let members = self.value.get(WORKSPACE_MEMBERS).and_then(Value::as_array);
+        self.value
+            .get(PACKAGES)?
+            .as_array()?
+            .iter()
+            .filter(|package| match members {
+                Some(members) => package.get(ID).is_some_and(|id| members.contains(id)),
+                None => true,
+            })
+            .find(|package| {
+                package
+                    .get(NAME)
+                    .and_then(Value::as_str)
+                    .is_some_and(|name| name.replace('-', "_") == package_name)
+            })
+    }
+
+    /**
+     * The names the package can give to its dependencies, including its dev-dependencies if `dev` is set:
+     * from the resolved graph if any, else from the dependencies it declares (`cargo metadata --no-deps`).
+     */
+    pub fn extern_crates(&self, package_name: &str, dev: bool) -> Option<Set<String>> {
+        let package = self.package(package_name)?;
+        let node = self
+            .value
+            .get(RESOLVE)
+            .and_then(|resolve| resolve.get(NODES))
+            .and_then(Value::as_array)
+            .and_then(|nodes| nodes.iter().find(|node| node.get(ID) == package.get(ID)));
+        if let Some(node) = node {
+            let crates = node
+                .get(DEPS)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter(|dependency| {
+                    let kinds = dependency.get(DEP_KINDS).and_then(Value::as_array);
+                    match kinds {
+                        Some(kinds) => {
+                            kinds.is_empty() || kinds.iter().any(|kind| is_available(kind, dev))
+                        }
+                        None => true,
+                    }
+                })
+                .filter_map(|dependency| dependency.get(NAME)?.as_str())
+                .map(String::from)
+                .collect();
+            return Some(crates);
+        }
+        let crates = package
+            .get(DEPENDENCIES)?
+            .as_array()?
+            .iter()
+            .filter(|dependency| is_available(dependency, dev))
+            .filter_map(|dependency| {
+                dependency
+                    .get(RENAME)
+                    .and_then(Value::as_str)
+                    .or_else(|| dependency.get(NAME)?.as_str())
+            })
+            .map(|name| name.replace('-', "_"))
+            .collect();
+        Some(crates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet as Set;
+
+    use super::Metadata;
+
+    #[test]
+    fn it_reads_the_resolved_dependencies() {
+        let metadata = Metadata::parse(
+            r#"{
+                "packages": [
+                    {"name": "my-app", "id": "app", "dependencies": []},
+                    {"name": "serde", "id": "serde", "dependencies": []}
+                ],
+                "workspace_members": ["app"],
+                "resolve": {"nodes": [
+                    {"id": "app", "deps": [
+                        {"name": "serde", "dep_kinds": [{"kind": null}]},
+                        {"name": "json", "dep_kinds": [{"kind": "dev"}]},
+                        {"name": "cc", "dep_kinds": [{"kind": "build"}]}
+                    ]}
+                ]}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            metadata.extern_crates("my_app", false),
+            Some(Set::from([String::from("serde")]))
+        );
+        assert_eq!(
+            metadata.extern_crates("my_app", true),
+            Some(Set::from([String::from("json"), String::from("serde")]))
+        );
+        assert_eq!(metadata.extern_crates("serde", false), None);
+    }
+
+    #[test]
+    fn it_reads_the_declared_dependencies_without_resolve() {
+        let metadata = Metadata::parse(
+            r#"{
+                "packages": [
+                    {"name": "my-app", "id": "app", "dependencies": [
+                        {"name": "serde-json", "rename": null, "kind": null},
+                        {"name": "rand", "rename": "old_rand", "kind": "dev"},
+                        {"name": "cc", "rename": null, "kind": "build"}
+                    ]}
+                ],
+                "workspace_members": ["app"],
+                "resolve": null
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            metadata.extern_crates("my_app", false),
+            Some(Set::from([String::from("serde_json")]))
+        );
+        assert_eq!(
+            metadata.extern_crates("my_app", true),
+            Some(Set::from([
+                String::from("old_rand"),
+                String::from("serde_json")
+            ]))
+        );
+    }
+}
@@ -0,0 +1,89 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    str::FromStr,
+};
+
+use crate::dependencies_graph::Reexports;
+
+/**
+ * The Rust edition of a package, which changes how the paths are resolved.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Edition {
+    E2015,
+    E2018,
+    #[default]
+    E2021,
+    E2024,
+}
+
+impl FromStr for Edition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2015" => Ok(Self::E2015),
+            "2018" => Ok(Self::E2018),
+            "2021" => Ok(Self::E2021),
+            "2024" => Ok(Self::E2024),
+            _ => Err(format!(
+                "Unknown edition: {} (expected 2015, 2018, 2021 or 2024).",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * What is known about the package whose graph is drawn, beyond its modules.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Package {
+    pub name: String,
+    /**
+     * The local names of external crates (`extern crate` aliases and Cargo renames),
+     * mapped to the crate names of their packages.
+     */
+    pub aliases: Map<String, String>,
+    /**
+     * The edition of the package: 2015 when the manifest gives none, as Cargo assumes,
+     * and the default one (2021) when there is no manifest.
+     */
+    pub edition: Edition,
+    /**
+     * The re-exports to follow in order to reach the modules defining the items, if requested.
+     */
+    pub reexports: Option<Reexports>,
+    /**
+     * The names of the crates in the extern prelude, when the dependencies are known from `cargo metadata`.
+     */
+    pub extern_crates: Option<Set<String>>,
+    /**
+     * The dev-dependencies, which the items under `#[cfg(test)]` can name as well.
+     */
+    pub dev_crates: Set<String>,
+    /**
+     * The keys of the roots of the binaries drawn along with the library as a single crate, other than `main`,
+     * such as `bin/foo/main` for `src/bin/foo/main.rs`: the modules of each one are keyed under its directory.
+     */
+    pub binaries: Set<Vec<String>>,
+    /**
+     * The members of a workspace by crate name, or the targets of a package by key,
+     * whose modules are nested under that name. Empty for a single crate.
+     */
+    pub members: Map<String, Package>,
+}
+
+impl Package {
+    pub fn new(name: &str) -> Self {
+        Package {
+            name: name.to_string(),
+            ..Package::default()
+        }
+    }
+}
@@ -1,7 +1,66 @@
-use crate::dependencies::DependencyPath;
+use std::collections::BTreeMap as Map;
+
+use crate::{cfg::CfgSet, dependencies::Dependency};
 
 pub mod rust_parser;
+pub mod syn_parser;
+
+/**
+ * A `mod foo;` declaration, whose contents live in another file,
+ * or an inline `mod foo { ... }` block, whose contents have been parsed along with its parent.
+ */
+#[derive(Debug, PartialEq)]
+pub struct ModuleDeclaration {
+    pub name: String,
+    pub test_only: bool,
+    /**
+     * The file given by a `#[path = "..."]` attribute, as written.
+     */
+    pub path: Option<String>,
+    /** `#[macro_use]` keeps the macros of the module in scope after its declaration. */
+    pub macro_use: bool,
+    /** Whether the declaration is compiled out by a `#[cfg(...)]` attribute. */
+    pub disabled: bool,
+    pub contents: Option<ParsedModule>,
+}
+
+/**
+ * A `macro_rules!` definition.
+ */
+#[derive(Debug, PartialEq)]
+pub struct MacroDefinition {
+    pub name: String,
+    /** `#[macro_export]` makes the macro available at the root of the crate. */
+    pub exported: bool,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ParsedModule {
+    pub dependencies: Vec<Dependency>,
+    pub submodules: Vec<ModuleDeclaration>,
+    /**
+     * The crates renamed by `extern crate foo as bar;`, from `bar` to `foo`.
+     */
+    pub aliases: Map<String, String>,
+    pub macros: Vec<MacroDefinition>,
+    /**
+     * The dependencies of each top-level item, by name; they are part of `dependencies` as well.
+     * The dependencies of an `impl` block belong to its type.
+     */
+    pub items: Map<String, Vec<Dependency>>,
+    /**
+     * Why the syntax tree parser fell back to regular expressions, if it did.
+     */
+    pub fallback: Option<String>,
+}
 
 pub trait Parser {
-    fn parse_dependencies(file_contents: &str) -> Vec<DependencyPath>;
-}
\ No newline at end of file
+    /**
+     * Dependencies and declarations of items annotated with `#[cfg(test)]` are tagged as test-only.
+     * Items compiled out according to `cfgs` are dropped, except for module declarations, which are flagged.
+     * Macro invocations are dependencies of the `Macro` kind, whose paths are resolved later on
+     * if they consist of a single name.
+     * Each dependency comes with the line and column it is found at.
+     */
+    fn parse_module(file_contents: &str, cfgs: &CfgSet) -> ParsedModule;
+}
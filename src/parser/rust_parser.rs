@@ -3,16 +3,35 @@
  * This file is part of Cargo Graphmod.
  * SPDX-License-Identifier: GPL-3.0-only
  */
-use std::collections::BTreeSet as Set;
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    ops::Range,
+};
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use crate::dependencies::DependencyPath;
+use crate::{
+    cfg::CfgSet,
+    dependencies::{Dependency, DependencyPath, EdgeKind, Span},
+};
 
-use super::Parser;
+use super::{MacroDefinition, ModuleDeclaration, ParsedModule, Parser};
 
 const INPUT_SEPARATOR: &str = "::";
+const SELF: &str = "self";
+const UNDERSCORE: &str = "_";
+const MACRO_RULES: &str = "macro_rules";
+const RAW_PREFIX: &str = "r#";
+const DERIVE: &str = "derive";
+/** The attributes whose arguments are not paths. */
+const PLAIN_ATTRIBUTES: [&str; 4] = ["cfg", "cfg_attr", "doc", "path"];
+/** The tools whose attributes, such as `#[rustfmt::skip]`, are not paths. */
+const TOOLS: [&str; 4] = ["clippy", "diagnostic", "rustdoc", "rustfmt"];
+const PRIMITIVE_TYPES: [&str; 17] = [
+    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "str", "u8", "u16",
+    "u32", "u64", "u128", "usize",
+];
 
 fn develop_innermost_dependencies(text: &str) -> Set<String> {
     lazy_static! {
@@ -55,22 +74,593 @@ fn develop_all_dependencies(dependency: &str) -> Set<String> {
     current_deps
 }
 
-fn keep_before_cfg_test(text: &str) -> Option<String> {
+/**
+ * Replaces comments and the contents of literals with spaces, so that they cannot be mistaken for code.
+ * Lines and columns are preserved. The strings of `#[path = "..."]` attributes are kept,
+ * as well as those given to a named argument of an attribute (`#[cfg(feature = "...")]`, `#[serde(with = "...")]`).
+ */
+struct Lexer {
+    chars: Vec<char>,
+    index: usize,
+    output: String,
+}
+
+impl Lexer {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.index + offset).copied()
+    }
+
+    fn keep(&mut self) {
+        self.output.push(self.chars[self.index]);
+        self.index += 1;
+    }
+
+    fn blank(&mut self) {
+        let c = self.chars[self.index];
+        self.output.push(if c == '\n' { c } else { ' ' });
+        self.index += 1;
+    }
+
+    fn line_comment(&mut self) {
+        while self.peek(0).is_some_and(|c| c != '\n') {
+            self.blank();
+        }
+    }
+
+    fn block_comment(&mut self) {
+        let mut depth = 0usize;
+        while let Some(c) = self.peek(0) {
+            match (c, self.peek(1)) {
+                ('/', Some('*')) => {
+                    depth += 1;
+                    self.blank();
+                    self.blank();
+                }
+                ('*', Some('/')) => {
+                    depth -= 1;
+                    self.blank();
+                    self.blank();
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => self.blank(),
+            }
+        }
+    }
+
+    fn is_attribute_value(&self) -> bool {
+        lazy_static! {
+            static ref PATH_ATTRIBUTE: Regex = Regex::new(r"^\#\[\s*path\s*=\s*$").unwrap();
+            static ref ARGUMENT: Regex =
+                Regex::new(r"^\#\[\s*(?:\w+::)*\w+\s*\([^\]]*=\s*$").unwrap();
+        }
+        let attribute = &self.output[self.output.rfind('#').unwrap_or(0)..];
+        self.output.trim_end().ends_with('=')
+            && (PATH_ATTRIBUTE.is_match(attribute) || ARGUMENT.is_match(attribute))
+    }
+
+    fn string(&mut self) {
+        let kept = self.is_attribute_value();
+        self.keep();
+        while let Some(c) = self.peek(0) {
+            match c {
+                '"' => return self.keep(),
+                '\\' if kept => {
+                    self.keep();
+                    if self.peek(0).is_some() {
+                        self.keep();
+                    }
+                }
+                '\\' => {
+                    self.blank();
+                    if self.peek(0).is_some() {
+                        self.blank();
+                    }
+                }
+                _ if kept => self.keep(),
+                _ => self.blank(),
+            }
+        }
+    }
+
+    /**
+     * The number of hashes of a raw string starting at the current `r`, if any.
+     */
+    fn raw_string_hashes(&self) -> Option<usize> {
+        let prefix = self
+            .output
+            .chars()
+            .rev()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect::<String>();
+        if !["", "b", "c"].contains(&prefix.as_str()) {
+            return None;
+        }
+        let hashes = (1..)
+            .take_while(|offset| self.peek(*offset) == Some('#'))
+            .count();
+        (self.peek(hashes + 1) == Some('"')).then_some(hashes)
+    }
+
+    fn raw_string(&mut self, hashes: usize) {
+        for _ in 0..hashes + 2 {
+            self.keep();
+        }
+        while self.peek(0).is_some() {
+            if self.peek(0) == Some('"')
+                && (1..=hashes).all(|offset| self.peek(offset) == Some('#'))
+            {
+                for _ in 0..hashes + 1 {
+                    self.keep();
+                }
+                return;
+            }
+            self.blank();
+        }
+    }
+
+    /**
+     * A quote starts either a character literal or a lifetime, which is kept.
+     */
+    fn quote(&mut self) {
+        match (self.peek(1), self.peek(2)) {
+            (Some('\\'), _) => {
+                self.keep();
+                self.blank();
+                if self.peek(0).is_some() {
+                    self.blank();
+                }
+                while self.peek(0).is_some_and(|c| c != '\'') {
+                    self.blank();
+                }
+                if self.peek(0).is_some() {
+                    self.keep();
+                }
+            }
+            (Some(_), Some('\'')) => {
+                self.keep();
+                self.blank();
+                self.keep();
+            }
+            _ => self.keep(),
+        }
+    }
+
+    fn run(mut self) -> String {
+        while let Some(c) = self.peek(0) {
+            match (c, self.peek(1)) {
+                ('/', Some('/')) => self.line_comment(),
+                ('/', Some('*')) => self.block_comment(),
+                ('"', _) => self.string(),
+                ('\'', _) => self.quote(),
+                ('r', _) => match self.raw_string_hashes() {
+                    Some(hashes) => self.raw_string(hashes),
+                    None => self.keep(),
+                },
+                _ => self.keep(),
+            }
+        }
+        self.output
+    }
+}
+
+fn blank_comments_and_literals(text: &str) -> String {
+    Lexer {
+        chars: text.chars().collect(),
+        index: 0,
+        output: String::with_capacity(text.len()),
+    }
+    .run()
+}
+
+/**
+ * Length of the item following an attribute: up to the first semicolon outside of any bracket,
+ * or up to the closing brace of its body.
+ */
+fn item_length(text: &str) -> usize {
+    let mut depth = 0usize;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    let after = text[index + 1..].trim_start();
+                    return if after.starts_with(';') {
+                        text.len() - after.len() + 1
+                    } else {
+                        index + 1
+                    };
+                }
+            }
+            ';' if depth == 0 => return index + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/**
+ * Length of the contents of parentheses, up to the matching closing parenthesis.
+ */
+fn parenthesized_length(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(index),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn blank(text: &str) -> String {
+    text.chars()
+        .map(|c| if c == '\n' { c } else { ' ' })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CfgPart {
+    Production,
+    Tests,
+    Disabled,
+}
+
+/**
+ * The text of a module, split by `#[cfg(...)]` attributes.
+ * Each part is blanked outside of its own items, so that lines and columns are preserved.
+ */
+#[derive(Default)]
+struct CfgItems {
+    production: String,
+    /** The items annotated with `#[cfg(test)]`. */
+    tests: String,
+    /** The items compiled out. */
+    disabled: String,
+}
+
+impl CfgItems {
+    fn push(&mut self, text: &str, part: CfgPart) {
+        let blanks = blank(text);
+        for (own_part, output) in [
+            (CfgPart::Production, &mut self.production),
+            (CfgPart::Tests, &mut self.tests),
+            (CfgPart::Disabled, &mut self.disabled),
+        ] {
+            output.push_str(if own_part == part { text } else { &blanks });
+        }
+    }
+}
+
+fn split_cfg_items(text: &str, cfgs: &CfgSet) -> CfgItems {
     lazy_static! {
-        static ref KEEP_BEFORE: Regex = Regex::new(r"(?sm)(.*?)\#\[cfg\(test\)\].*").unwrap();
+        static ref CFG: Regex = Regex::new(r"\#\[\s*cfg\s*\(").unwrap();
+        static ref ATTRIBUTE_END: Regex = Regex::new(r"^\)\s*\]").unwrap();
+    }
+    let mut items = CfgItems::default();
+    let mut rest = text;
+    while let Some(attribute) = CFG.find(rest) {
+        let predicate = &rest[attribute.end()..];
+        let Some((length, item_start)) = parenthesized_length(predicate).and_then(|length| {
+            Some((
+                length,
+                length + ATTRIBUTE_END.find(&predicate[length..])?.end(),
+            ))
+        }) else {
+            items.push(&rest[..attribute.end()], CfgPart::Production);
+            rest = predicate;
+            continue;
+        };
+        let part = if cfgs.is_test_only(&predicate[..length]) {
+            CfgPart::Tests
+        } else if cfgs.disables(&predicate[..length]) {
+            CfgPart::Disabled
+        } else {
+            items.push(&rest[..attribute.end() + item_start], CfgPart::Production);
+            rest = &predicate[item_start..];
+            continue;
+        };
+        items.push(&rest[..attribute.start()], CfgPart::Production);
+        // the attribute stays with its item, so that declarations keep starting their lines
+        let item = &rest[attribute.start()..];
+        let length = attribute.end() - attribute.start() + item_start;
+        let length = length + item_length(&item[length..]);
+        items.push(&item[..length], part);
+        rest = &item[length..];
+    }
+    items.push(rest, CfgPart::Production);
+    items
+}
+
+/**
+ * The line and column at a byte offset of a text.
+ */
+fn span_at(text: &str, offset: usize) -> Span {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Span {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+        ..Span::default()
     }
-    KEEP_BEFORE.captures(text).map(|cap| cap[1].to_string())
 }
 
-fn parse_use(text: &str) -> Vec<String> {
+/**
+ * The imported paths, whether they are re-exported by `pub use`, and the offsets of their `use` keywords.
+ */
+fn parse_use(text: &str) -> Vec<(String, bool, usize)> {
     lazy_static! {
-        static ref USE: Regex = Regex::new(r"(?sm)^(?:\s)*(?:pub )?use (.*?);").unwrap();
+        static ref USE: Regex =
+            Regex::new(r"(?sm)^(?:\s)*(?:\#\[[^\]]*\]\s*)*(pub(?:\([^)]*\))? )?(use) (.*?);")
+                .unwrap();
     }
     USE.captures_iter(text)
-        .map(|cap| cap[1].to_string())
+        .filter_map(|cap| {
+            Some((
+                cap[3].to_string(),
+                cap.get(1).is_some(),
+                cap.get(2)?.start(),
+            ))
+        })
+        .collect()
+}
+
+/**
+ * The value of the `#[path]` attribute among some attributes, if any,
+ * and whether they contain `#[macro_use]`.
+ */
+fn declaration_attributes(attributes: &str) -> (Option<String>, bool) {
+    lazy_static! {
+        static ref PATH: Regex = Regex::new(r#"\#\[\s*path\s*=\s*"([^"]*)"\s*\]"#).unwrap();
+        static ref MACRO_USE: Regex = Regex::new(r"\#\[\s*macro_use\s*\]").unwrap();
+    }
+    (
+        PATH.captures(attributes).map(|path| path[1].to_string()),
+        MACRO_USE.is_match(attributes),
+    )
+}
+
+/**
+ * Only the unindented declarations are kept, as the other ones belong to the bodies of items;
+ * inline modules are blanked out of the text of their parent beforehand.
+ * The name of each module comes with the value of its `#[path]` attribute, if any,
+ * and whether it is annotated with `#[macro_use]`.
+ */
+fn parse_mod(text: &str) -> Vec<(String, Option<String>, bool)> {
+    lazy_static! {
+        static ref MOD: Regex = Regex::new(
+            r"(?m)^((?:\#\[[^\]]*\]\s*)*)(?:pub(?:\([^)]*\))?\s+)?mod\s+((?:r#)?\w+)\s*;"
+        )
+        .unwrap();
+    }
+    MOD.captures_iter(text)
+        .map(|cap| {
+            let (path, macro_use) = declaration_attributes(&cap[1]);
+            (unraw(&cap[2]).to_string(), path, macro_use)
+        })
+        .collect()
+}
+
+/**
+ * An inline module `mod foo { ... }`, located by byte offsets in the text of its parent.
+ */
+struct InlineModule {
+    name: String,
+    attributes: String,
+    /** The module along with its attributes. */
+    item: Range<usize>,
+    /** Between the braces. */
+    contents: Range<usize>,
+}
+
+/**
+ * Only the outermost inline modules are found: the nested ones belong to their contents.
+ */
+fn find_inline_modules(text: &str) -> Vec<InlineModule> {
+    lazy_static! {
+        static ref INLINE_MOD: Regex = Regex::new(
+            r"(?m)^[ \t]*((?:\#\[[^\]]*\]\s*)*)(?:pub(?:\([^)]*\))?\s+)?mod\s+((?:r#)?\w+)\s*\{"
+        )
+        .unwrap();
+    }
+    let mut modules: Vec<InlineModule> = vec![];
+    for cap in INLINE_MOD.captures_iter(text) {
+        let (Some(all), Some(attributes)) = (cap.get(0), cap.get(1)) else {
+            continue;
+        };
+        if modules
+            .last()
+            .is_some_and(|last| attributes.start() < last.item.end)
+        {
+            continue;
+        }
+        let brace = all.end() - 1;
+        let end = brace + item_length(&text[brace..]);
+        modules.push(InlineModule {
+            name: unraw(&cap[2]).to_string(),
+            attributes: attributes.as_str().to_string(),
+            item: attributes.start()..end,
+            contents: brace + 1..end.saturating_sub(1).max(brace + 1),
+        });
+    }
+    modules
+}
+
+/**
+ * The predicates of the `#[cfg(...)]` attributes among some attributes.
+ */
+fn cfg_predicates(attributes: &str) -> Vec<&str> {
+    lazy_static! {
+        static ref CFG: Regex = Regex::new(r"\#\[\s*cfg\s*\(").unwrap();
+    }
+    CFG.find_iter(attributes)
+        .filter_map(|attribute| {
+            let predicate = &attributes[attribute.end()..];
+            Some(&predicate[..parenthesized_length(predicate)?])
+        })
+        .collect()
+}
+
+/**
+ * The text of an inline module, blanked before its contents so that lines are preserved,
+ * and unindented so that its items start their lines, along with the number of columns removed.
+ */
+fn inline_module_text(text: &str, contents: &Range<usize>) -> (String, usize) {
+    let text = blank(&text[..contents.start]) + &text[contents.clone()];
+    let indentation = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().take_while(|c| c.is_whitespace()).count())
+        .min()
+        .unwrap_or(0);
+    let unindented = text
+        .split('\n')
+        .map(|line| line.chars().skip(indentation).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n");
+    (unindented, indentation)
+}
+
+/**
+ * Moves the spans of a module parsed from an unindented text back to their columns.
+ */
+fn indent(module: &mut ParsedModule, columns: usize) {
+    for dependency in module
+        .dependencies
+        .iter_mut()
+        .chain(module.items.values_mut().flatten())
+    {
+        if let Some(span) = &mut dependency.span {
+            span.column += columns;
+        }
+    }
+    for contents in module
+        .submodules
+        .iter_mut()
+        .filter_map(|submodule| submodule.contents.as_mut())
+    {
+        indent(contents, columns);
+    }
+}
+
+/**
+ * Everything in an inline module annotated with `#[cfg(test)]` is only compiled for the tests.
+ */
+fn tag_test_only(module: &mut ParsedModule) {
+    for dependency in module
+        .dependencies
+        .iter_mut()
+        .chain(module.items.values_mut().flatten())
+    {
+        dependency.test_only = true;
+    }
+    for submodule in &mut module.submodules {
+        submodule.test_only = true;
+        if let Some(contents) = &mut submodule.contents {
+            tag_test_only(contents);
+        }
+    }
+}
+
+/**
+ * Inline modules are parsed with their own scope, unless they are compiled out.
+ */
+fn parse_inline_module(text: &str, module: &InlineModule, cfgs: &CfgSet) -> ModuleDeclaration {
+    let predicates = cfg_predicates(&module.attributes);
+    let test_only = predicates
+        .iter()
+        .any(|predicate| cfgs.is_test_only(predicate));
+    let disabled = !test_only && predicates.iter().any(|predicate| cfgs.disables(predicate));
+    let (path, macro_use) = declaration_attributes(&module.attributes);
+    let contents = (!disabled).then(|| {
+        let (contents, indentation) = inline_module_text(text, &module.contents);
+        let mut parsed = RustParser::parse_module(&contents, cfgs);
+        indent(&mut parsed, indentation);
+        if test_only {
+            tag_test_only(&mut parsed);
+        }
+        parsed
+    });
+    ModuleDeclaration {
+        name: module.name.clone(),
+        test_only,
+        path,
+        macro_use,
+        disabled,
+        contents,
+    }
+}
+
+fn parse_macro_rules(text: &str) -> Vec<MacroDefinition> {
+    lazy_static! {
+        static ref MACRO_RULES: Regex =
+            Regex::new(r"(?m)^\s*((?:\#\[[^\]]*\]\s*)*)macro_rules!\s*(\w+)").unwrap();
+        static ref MACRO_EXPORT: Regex = Regex::new(r"\#\[\s*macro_export\b").unwrap();
+    }
+    MACRO_RULES
+        .captures_iter(text)
+        .map(|cap| MacroDefinition {
+            name: cap[2].to_string(),
+            exported: MACRO_EXPORT.is_match(&cap[1]),
+        })
+        .collect()
+}
+
+/**
+ * The paths of the invoked macros, such as `log_event!(...)` or `crate::log_event![...]`, with their offsets.
+ */
+fn parse_macro_invocations(text: &str) -> Vec<(DependencyPath, usize)> {
+    lazy_static! {
+        static ref INVOCATION: Regex = Regex::new(r"\b((?:\w+::)*\w+)!\s*[(\[{]").unwrap();
+    }
+    INVOCATION
+        .captures_iter(text)
+        .filter(|cap| &cap[1] != MACRO_RULES)
+        .filter_map(|cap| {
+            Some((
+                DependencyPath(cap[1].split(INPUT_SEPARATOR).map(String::from).collect()),
+                cap.get(1)?.start(),
+            ))
+        })
         .collect()
 }
 
+/**
+ * The crates declared by `extern crate`, with their aliases and the offsets of their declarations.
+ */
+fn parse_extern_crate(text: &str) -> Vec<(String, Option<String>, usize)> {
+    lazy_static! {
+        static ref EXTERN_CRATE: Regex = Regex::new(
+            r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?(extern)\s+crate\s+(\w+)(?:\s+as\s+(\w+))?\s*;"
+        )
+        .unwrap();
+    }
+    EXTERN_CRATE
+        .captures_iter(text)
+        .filter(|cap| &cap[2] != SELF)
+        .filter_map(|cap| {
+            let alias = cap.get(3).map(|alias| alias.as_str().to_string());
+            Some((
+                cap[2].to_string(),
+                alias.filter(|alias| alias != UNDERSCORE),
+                cap.get(1)?.start(),
+            ))
+        })
+        .collect()
+}
+
+/**
+ * A raw identifier such as `r#type` names the module `type`.
+ */
+fn unraw(name: &str) -> &str {
+    name.strip_prefix(RAW_PREFIX).unwrap_or(name)
+}
+
 fn trim_spaces_and_as(dependency: &str) -> String {
     let mut vector = dependency.split_whitespace().collect::<Vec<_>>();
     let mut last_words = dependency.split_whitespace().rev();
@@ -82,24 +672,427 @@ fn trim_spaces_and_as(dependency: &str) -> String {
     vector.join("")
 }
 
-pub struct RustParser {}
+/**
+ * The name a developed import is bound to: its alias, or its last segment.
+ */
+fn binding(dependency: &str) -> String {
+    let mut words = dependency.split_whitespace().rev();
+    let name = match (words.next(), words.next()) {
+        (Some(alias), Some("as")) => alias,
+        _ => {
+            let mut segments = dependency.rsplit(INPUT_SEPARATOR).map(str::trim);
+            match segments.next() {
+                Some(SELF) => segments.next().unwrap_or(SELF),
+                last => last.unwrap_or_default(),
+            }
+        }
+    };
+    unraw(name).to_string()
+}
 
-impl Parser for RustParser {
-    fn parse_dependencies(file_contents: &str) -> Vec<DependencyPath> {
-        let before_tests = match keep_before_cfg_test(file_contents) {
-            None => file_contents.to_string(),
-            Some(found) => found,
+/**
+ * The imported dependencies, along with the names they are bound to.
+ * A leading `::` is dropped, and recorded as `global` by the dependency.
+ */
+fn parse_imports(text: &str) -> Vec<(String, Dependency)> {
+    parse_use(text)
+        .iter()
+        .flat_map(|(s, reexported, offset)| {
+            develop_all_dependencies(s).into_iter().map(|s| {
+                let segments = s
+                    .split(INPUT_SEPARATOR)
+                    .map(trim_spaces_and_as)
+                    .collect::<Vec<String>>();
+                let dependency = Dependency {
+                    reexported: *reexported,
+                    rooted: true,
+                    global: segments.first().is_some_and(String::is_empty),
+                    span: Some(span_at(text, *offset)),
+                    ..DependencyPath(
+                        segments
+                            .iter()
+                            .skip_while(|segment| segment.is_empty())
+                            .map(|segment| unraw(segment).to_string())
+                            .collect::<Vec<String>>(),
+                    )
+                    .into()
+                };
+                (binding(&s), dependency)
+            })
+        })
+        .collect()
+}
+
+/**
+ * An unindented item along with its attributes, named after its type for an `impl` block.
+ */
+struct FoundItem<'a> {
+    name: String,
+    is_impl: bool,
+    /** The offset of the item in the text of its module. */
+    start: usize,
+    text: &'a str,
+}
+
+fn find_items(text: &str) -> Vec<FoundItem<'_>> {
+    lazy_static! {
+        static ref ITEM: Regex = Regex::new(concat!(
+            r"(?m)^(?:\#\[[^\]]*\]\s*)*",
+            r#"(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe|extern\s+"[^"]*")\s+)*"#,
+            r"(?:(?:struct|enum|union|trait|type|fn|const|static)\s+(?:mut\s+)?(\w+)",
+            r"|impl\b[^{;]*?(\w+)\s*(?:<[^{;]*>)?\s*(?:where\b[^{;]*)?\{)"
+        ))
+        .unwrap();
+    }
+    ITEM.captures_iter(text)
+        .filter_map(|cap| {
+            let start = cap.get(0)?.start();
+            let (name, is_impl) = match (cap.get(1), cap.get(2)) {
+                (Some(name), _) => (name, false),
+                (None, Some(name)) => (name, true),
+                (None, None) => return None,
+            };
+            Some(FoundItem {
+                name: name.as_str().to_string(),
+                is_impl,
+                start,
+                text: &text[start..start + item_length(&text[start..])],
+            })
+        })
+        .collect()
+}
+
+/**
+ * An item depends on the imports and on the other items of its module that it mentions,
+ * as well as on the macros it invokes. A name is located at its first mention.
+ */
+fn parse_items(text: &str, imports: &[(String, Dependency)]) -> Map<String, Vec<Dependency>> {
+    lazy_static! {
+        static ref WORD: Regex = Regex::new(r"\w+").unwrap();
+    }
+    let found = find_items(text);
+    let mut items = found
+        .iter()
+        .filter(|item| !item.is_impl)
+        .map(|item| (item.name.clone(), vec![]))
+        .collect::<Map<_, Vec<Dependency>>>();
+    let defined = items.keys().cloned().collect::<Set<_>>();
+    for item in found {
+        let Some(dependencies) = items.get_mut(&item.name) else {
+            continue;
         };
-        parse_use(&before_tests)
+        let mut words = Map::new();
+        for word in WORD.find_iter(item.text) {
+            words
+                .entry(word.as_str())
+                .or_insert(item.start + word.start());
+        }
+        // the imports named by a trait impl, derive or attribute only are marked as such
+        let marked =
+            resolve_marked_paths(&text[..item.start + item.text.len()], item.start, imports);
+        dependencies.extend(
+            imports
+                .iter()
+                .filter(|(_, dependency)| {
+                    !marked.iter().any(|marked| marked.path == dependency.path)
+                })
+                .filter_map(|(binding, dependency)| {
+                    Some(Dependency {
+                        span: Some(span_at(text, *words.get(binding.as_str())?)),
+                        ..dependency.clone()
+                    })
+                }),
+        );
+        dependencies.extend(marked);
+        dependencies.extend(
+            defined
+                .iter()
+                .filter(|other| **other != item.name)
+                .filter_map(|other| {
+                    Some(Dependency {
+                        span: Some(span_at(text, *words.get(other.as_str())?)),
+                        ..DependencyPath(vec![SELF.to_string(), other.clone()]).into()
+                    })
+                }),
+        );
+        dependencies.extend(parse_macro_invocations(item.text).into_iter().map(
+            |(path, offset)| Dependency {
+                path,
+                kind: EdgeKind::Macro,
+                span: Some(span_at(text, item.start + offset)),
+                ..Dependency::default()
+            },
+        ));
+    }
+    items
+}
+
+/**
+ * The traits of the `impl ... for` blocks, the derives, the attribute macros,
+ * and the paths given to attributes as strings (`#[serde(with = "crate::time_fmt")]`), with their offsets.
+ */
+fn parse_marked_paths(text: &str) -> Vec<(DependencyPath, EdgeKind, usize)> {
+    lazy_static! {
+        static ref IMPL: Regex = Regex::new(
+            r"\bimpl\b\s*(?:<[^{;]*?>\s*)?!?\s*((?:\w+::)*\w+)\s*(?:<[^{;]*?>\s*)?for\b"
+        )
+        .unwrap();
+        static ref ATTRIBUTE: Regex = Regex::new(r"\#!?\[\s*((?:\w+::)*\w+)([^\]]*)\]").unwrap();
+        static ref PATH: Regex = Regex::new(r"(?:\w+::)*\w+").unwrap();
+        static ref STRING_PATH: Regex = Regex::new(r#""((?:\w+::)+\w+)""#).unwrap();
+    }
+    let make_path =
+        |path: &str| DependencyPath(path.split(INPUT_SEPARATOR).map(String::from).collect());
+    let mut paths = IMPL
+        .captures_iter(text)
+        .filter_map(|cap| {
+            let path = cap.get(1)?;
+            Some((make_path(path.as_str()), EdgeKind::Implements, path.start()))
+        })
+        .collect::<Vec<_>>();
+    for cap in ATTRIBUTE.captures_iter(text) {
+        let (Some(name), Some(arguments)) = (cap.get(1), cap.get(2)) else {
+            continue;
+        };
+        if name.as_str() == DERIVE {
+            paths.extend(PATH.find_iter(arguments.as_str()).map(|path| {
+                (
+                    make_path(path.as_str()),
+                    EdgeKind::Derives,
+                    arguments.start() + path.start(),
+                )
+            }));
+            continue;
+        }
+        if PLAIN_ATTRIBUTES.contains(&name.as_str()) {
+            continue;
+        }
+        let tool = TOOLS
             .iter()
-            .flat_map(|s| develop_all_dependencies(s))
-            .map(|s| {
-                s.split(INPUT_SEPARATOR)
-                    .map(trim_spaces_and_as)
-                    .collect::<Vec<String>>()
+            .any(|tool| name.as_str().split(INPUT_SEPARATOR).next() == Some(tool));
+        if !tool {
+            paths.push((make_path(name.as_str()), EdgeKind::Attribute, name.start()));
+        }
+        paths.extend(
+            STRING_PATH
+                .captures_iter(arguments.as_str())
+                .filter_map(|cap| {
+                    let path = cap.get(1)?;
+                    Some((
+                        make_path(path.as_str()),
+                        EdgeKind::Attribute,
+                        arguments.start() + path.start(),
+                    ))
+                }),
+        );
+    }
+    paths
+}
+
+/**
+ * Whether a path such as `foo::bar` may start with a module or a crate,
+ * rather than with a type (`Vec::new`, `Self::new`, `u8::MAX`).
+ */
+fn may_start_with_module(path: &[String]) -> bool {
+    match path.first() {
+        Some(fst) if path.len() > 1 => {
+            fst.starts_with(|c: char| c.is_lowercase() || c == '_')
+                && !PRIMITIVE_TYPES.contains(&fst.as_str())
+        }
+        _ => false,
+    }
+}
+
+/**
+ * The qualified paths of expressions, types, trait bounds, struct literals and patterns
+ * (`crate::foo::bar()`, `super::Thing::new()`), whether they start with `::`, and their offsets.
+ * The `use` declarations, attributes, visibilities and macro invocations are left to their own parsers.
+ */
+fn parse_qualified_paths(text: &str) -> Vec<(DependencyPath, bool, usize)> {
+    lazy_static! {
+        static ref SKIPPED: Regex =
+            Regex::new(r"(?s)\buse\s[^;]*;|\#!?\[[^\]]*\]|\bpub\s*\(\s*in\s[^)]*\)").unwrap();
+        static ref QUALIFIED: Regex =
+            Regex::new(r"(?:^|[^\w:$])((::)?(?:(?:r#)?\w+::)+(?:r#)?\w+)(\s*!)?").unwrap();
+    }
+    let mut text = text.to_string();
+    for skipped in SKIPPED
+        .find_iter(&text.clone())
+        .collect::<Vec<_>>()
+        .iter()
+        .rev()
+    {
+        text.replace_range(skipped.range(), &blank(skipped.as_str()));
+    }
+    QUALIFIED
+        .captures_iter(&text)
+        .filter(|cap| cap.get(3).is_none())
+        .filter_map(|cap| {
+            let path = cap.get(1)?;
+            Some((
+                DependencyPath(
+                    path.as_str()
+                        .split(INPUT_SEPARATOR)
+                        .skip_while(|segment| segment.is_empty())
+                        .map(|segment| unraw(segment).to_string())
+                        .collect(),
+                ),
+                cap.get(2).is_some(),
+                path.start(),
+            ))
+        })
+        .collect()
+}
+
+/**
+ * The marked and qualified paths found in a text from `offset` on,
+ * whose first segment is resolved against the imports.
+ * A single marked name which is not imported is dropped, as it belongs to the prelude,
+ * and so is a qualified path which is not imported and starts with a type.
+ */
+fn resolve_marked_paths(
+    text: &str,
+    offset: usize,
+    imports: &[(String, Dependency)],
+) -> Vec<Dependency> {
+    let marked = parse_marked_paths(&text[offset..]);
+    // the trait of an `impl ... for` block is drawn as such only
+    let qualified = parse_qualified_paths(&text[offset..])
+        .into_iter()
+        .filter(|(_, global, start)| {
+            let first = start + if *global { INPUT_SEPARATOR.len() } else { 0 };
+            !marked.iter().any(|(_, _, marked)| *marked == first)
+        })
+        .map(|(path, global, start)| (path, EdgeKind::Use, global, start))
+        .collect::<Vec<_>>();
+    marked
+        .into_iter()
+        .map(|(path, kind, start)| (path, kind, false, start))
+        .chain(qualified)
+        .filter_map(|(DependencyPath(path), kind, global, start)| {
+            let import = imports
+                .iter()
+                .find(|(binding, _)| !global && Some(binding) == path.first());
+            let (path, rooted, global) = match import {
+                Some((_, dependency)) => (
+                    [&dependency.path.0, &path[1..]].concat(),
+                    true,
+                    dependency.global,
+                ),
+                None if global => (path, true, true),
+                None if kind == EdgeKind::Use && !may_start_with_module(&path) => return None,
+                None if path.len() > 1 => (path, false, false),
+                None => return None,
+            };
+            Some(Dependency {
+                path: DependencyPath(path),
+                kind,
+                rooted,
+                global,
+                span: Some(span_at(text, offset + start)),
+                ..Dependency::default()
             })
-            .map(DependencyPath)
-            .collect()
+        })
+        .collect()
+}
+
+fn parse_dependencies(text: &str) -> Vec<Dependency> {
+    let imports = parse_imports(text);
+    let marked = resolve_marked_paths(text, 0, &imports);
+    imports
+        .into_iter()
+        .map(|(_, dependency)| dependency)
+        .chain(marked)
+        .chain(
+            parse_extern_crate(text)
+                .into_iter()
+                .map(|(name, _, offset)| Dependency {
+                    span: Some(span_at(text, offset)),
+                    ..DependencyPath(vec![name]).into()
+                }),
+        )
+        .chain(
+            parse_macro_invocations(text)
+                .into_iter()
+                .map(|(path, offset)| Dependency {
+                    path,
+                    kind: EdgeKind::Macro,
+                    span: Some(span_at(text, offset)),
+                    ..Dependency::default()
+                }),
+        )
+        .collect()
+}
+
+pub struct RustParser {}
+
+fn parse_module_declarations(
+    text: &str,
+    test_only: bool,
+    disabled: bool,
+) -> Vec<ModuleDeclaration> {
+    parse_mod(text)
+        .into_iter()
+        .map(|(name, path, macro_use)| ModuleDeclaration {
+            name,
+            test_only,
+            path,
+            macro_use,
+            disabled,
+            contents: None,
+        })
+        .collect()
+}
+
+impl Parser for RustParser {
+    fn parse_module(file_contents: &str, cfgs: &CfgSet) -> ParsedModule {
+        let text = blank_comments_and_literals(file_contents);
+        let inline_modules = find_inline_modules(&text);
+        let mut own_text = text.clone();
+        // from the last module on, so that the offsets of the previous ones still hold
+        for module in inline_modules.iter().rev() {
+            own_text.replace_range(module.item.clone(), &blank(&text[module.item.clone()]));
+        }
+        let CfgItems {
+            production,
+            tests,
+            disabled,
+        } = split_cfg_items(&own_text, cfgs);
+        let dependencies = parse_dependencies(&production)
+            .into_iter()
+            .chain(
+                parse_dependencies(&tests)
+                    .into_iter()
+                    .map(|dependency| Dependency {
+                        test_only: true,
+                        ..dependency
+                    }),
+            )
+            .collect();
+        let mut submodules = parse_module_declarations(&production, false, false);
+        submodules.append(&mut parse_module_declarations(&tests, true, false));
+        submodules.append(&mut parse_module_declarations(&disabled, false, true));
+        submodules.extend(
+            inline_modules
+                .iter()
+                .map(|module| parse_inline_module(&text, module, cfgs)),
+        );
+        let aliases = parse_extern_crate(&production)
+            .into_iter()
+            .chain(parse_extern_crate(&tests))
+            .filter_map(|(name, alias, _)| Some((alias?, name)))
+            .collect();
+        let mut macros = parse_macro_rules(&production);
+        macros.append(&mut parse_macro_rules(&tests));
+        let items = parse_items(&production, &parse_imports(&production));
+        ParsedModule {
+            dependencies,
+            submodules,
+            aliases,
+            macros,
+            items,
+            fallback: None,
+        }
     }
 }
 
@@ -108,17 +1101,22 @@ mod tests {
     use std::collections::BTreeSet as Set;
 
     use crate::{
-        dependencies::DependencyPath,
+        cfg::CfgSet,
+        dependencies::{Dependency, DependencyPath, EdgeKind, Span},
         parser::{
             rust_parser::{
-                develop_all_dependencies, develop_innermost_dependencies, parse_use,
+                develop_all_dependencies, develop_innermost_dependencies, parse_extern_crate,
+                parse_macro_invocations, parse_macro_rules, parse_mod, parse_use,
                 trim_spaces_and_as, RustParser,
             },
-            Parser,
+            MacroDefinition, ModuleDeclaration, Parser,
         },
     };
 
-    use super::keep_before_cfg_test;
+    use super::{
+        blank_comments_and_literals, parse_dependencies, parse_imports, parse_items,
+        split_cfg_items,
+    };
 
     #[test]
     fn it_develops_innermost() {
@@ -214,29 +1212,490 @@ mod tests {
 
     #[test]
     fn it_parses_multiple_use() {
-        let text = "use foo::bar;\npub use bar::foo;\n\tuse foobar;";
+        let text = "use foo::bar;\npub use bar::foo;\n\tuse foobar;\npub(crate) use baz::*;";
         let result = parse_use(text);
         assert_eq!(
             result,
             vec![
-                String::from("foo::bar"),
-                String::from("bar::foo"),
-                String::from("foobar")
+                (String::from("foo::bar"), false, 0),
+                (String::from("bar::foo"), true, 18),
+                (String::from("foobar"), false, 33),
+                (String::from("baz::*"), true, 56)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_flags_glob_imports_and_reexports() {
+        let text = "pub use self::inner::*;\nuse other::{a, b::*};";
+        let mut result = RustParser::parse_module(text, &CfgSet::default())
+            .dependencies
+            .into_iter()
+            .map(|dependency| (dependency.path, dependency.glob, dependency.reexported))
+            .collect::<Vec<_>>();
+        result.sort();
+        assert_eq!(
+            result,
+            vec![
+                (
+                    DependencyPath(vec![String::from("other"), String::from("a")]),
+                    false,
+                    false
+                ),
+                (
+                    DependencyPath(vec![String::from("other"), String::from("b")]),
+                    true,
+                    false
+                ),
+                (
+                    DependencyPath(vec![String::from("self"), String::from("inner")]),
+                    true,
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_extern_crate() {
+        let text = "extern crate serde_json as json;\npub extern crate regex;\nextern crate self as my_crate;\nextern crate std as _;";
+        let result = parse_extern_crate(text);
+        assert_eq!(
+            result,
+            vec![
+                (String::from("serde_json"), Some(String::from("json")), 0),
+                (String::from("regex"), None, 37),
+                (String::from("std"), None, 88)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_macros() {
+        let text = "#[macro_export]\nmacro_rules! log_event {\n    ($e:expr) => { println!(\"{}\", $e) };\n}\nmacro_rules! local { () => {} }\nfn f() {\n    log_event!(1);\n    crate::util::check! [2];\n    let v = vec![3];\n}";
+        assert_eq!(
+            parse_macro_rules(text),
+            vec![
+                MacroDefinition {
+                    name: String::from("log_event"),
+                    exported: true
+                },
+                MacroDefinition {
+                    name: String::from("local"),
+                    exported: false
+                }
+            ]
+        );
+        assert_eq!(
+            parse_macro_invocations(text),
+            vec![
+                (DependencyPath(vec![String::from("println")]), 60),
+                (DependencyPath(vec![String::from("log_event")]), 129),
+                (
+                    DependencyPath(vec![
+                        String::from("crate"),
+                        String::from("util"),
+                        String::from("check")
+                    ]),
+                    148
+                ),
+                (DependencyPath(vec![String::from("vec")]), 185)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_collects_the_dependencies_of_items() {
+        let text = r#"
+use crate::domain::Domain;
+use std::fmt;
+pub struct Report {
+    domain: Domain,
+}
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", render(self))
+    }
+}
+fn render(report: &Report) -> String {
+    String::new()
+}
+        "#;
+        let items = parse_items(text, &parse_imports(text));
+        let paths = |name: &str| {
+            items[name]
+                .iter()
+                .map(|dependency| dependency.path.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(items.keys().collect::<Vec<_>>(), vec!["Report", "render"]);
+        assert_eq!(
+            paths("Report"),
+            vec![
+                DependencyPath(vec![
+                    String::from("crate"),
+                    String::from("domain"),
+                    String::from("Domain")
+                ]),
+                DependencyPath(vec![String::from("std"), String::from("fmt")]),
+                DependencyPath(vec![
+                    String::from("std"),
+                    String::from("fmt"),
+                    String::from("Display")
+                ]),
+                DependencyPath(vec![
+                    String::from("std"),
+                    String::from("fmt"),
+                    String::from("Formatter")
+                ]),
+                DependencyPath(vec![
+                    String::from("std"),
+                    String::from("fmt"),
+                    String::from("Result")
+                ]),
+                DependencyPath(vec![String::from("self"), String::from("render")]),
+                DependencyPath(vec![String::from("write")]),
+            ]
+        );
+        assert_eq!(
+            paths("render"),
+            vec![DependencyPath(vec![
+                String::from("self"),
+                String::from("Report")
+            ])]
+        );
+        // a name is located at its first mention in the item
+        let span = |name: &str, index: usize| {
+            let span = items[name][index].span.clone().unwrap();
+            (span.line, span.column)
+        };
+        assert_eq!(span("Report", 0), (5, 13));
+        assert_eq!(span("render", 0), (12, 20));
+    }
+
+    #[test]
+    fn it_parses_macro_use() {
+        let text = "#[macro_use]\nmod macros;\nmod other;";
+        let result = parse_mod(text);
+        assert_eq!(
+            result,
+            vec![
+                (String::from("macros"), None, true),
+                (String::from("other"), None, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_mod() {
+        let text =
+            "pub mod foo;\npub(crate) mod bar ;\nmod inline {\n    mod nested;\n}\nmod r#type;";
+        let result = parse_mod(text);
+        assert_eq!(
+            result,
+            vec![
+                (String::from("foo"), None, false),
+                (String::from("bar"), None, false),
+                (String::from("type"), None, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_normalizes_absolute_paths_and_raw_identifiers() {
+        let text = "use ::serde::Serialize;\nuse crate::r#type::{r#async, Kind as r#Match};\nmod r#type;\nfn f(x: r#Match) {}";
+        let module = RustParser::parse_module(text, &CfgSet::default());
+        let global = module
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.global)
+            .map(|dependency| dependency.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            global,
+            vec![DependencyPath(vec![
+                String::from("serde"),
+                String::from("Serialize")
+            ])]
+        );
+        let mut result = module
+            .dependencies
+            .into_iter()
+            .map(|dependency| dependency.path)
+            .collect::<Vec<_>>();
+        result.sort();
+        assert_eq!(
+            result,
+            vec![
+                DependencyPath(vec![
+                    String::from("crate"),
+                    String::from("type"),
+                    String::from("Kind")
+                ]),
+                DependencyPath(vec![
+                    String::from("crate"),
+                    String::from("type"),
+                    String::from("async")
+                ]),
+                DependencyPath(vec![String::from("serde"), String::from("Serialize")]),
+            ]
+        );
+        assert_eq!(module.submodules[0].name, "type");
+        assert_eq!(
+            module.items["f"][0].path,
+            DependencyPath(vec![
+                String::from("crate"),
+                String::from("type"),
+                String::from("Kind")
+            ])
+        );
+    }
+
+    #[test]
+    fn it_parses_trait_impls_derives_and_attributes() {
+        let text = "use crate::ports::Storage;\n#[derive(Debug, crate::macros::Builder)]\n#[serde(with = \"crate::time_fmt\")]\nstruct Db;\nimpl Storage for Db {}\n#[rustfmt::skip]\n#[tokio::main]\nasync fn main() {}";
+        let result = parse_dependencies(text)
+            .into_iter()
+            .filter(|dependency| dependency.kind != EdgeKind::Use)
+            .map(|dependency| (dependency.path, dependency.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                (
+                    DependencyPath(vec![
+                        String::from("crate"),
+                        String::from("ports"),
+                        String::from("Storage")
+                    ]),
+                    EdgeKind::Implements
+                ),
+                (
+                    DependencyPath(vec![
+                        String::from("crate"),
+                        String::from("macros"),
+                        String::from("Builder")
+                    ]),
+                    EdgeKind::Derives
+                ),
+                (
+                    DependencyPath(vec![String::from("crate"), String::from("time_fmt")]),
+                    EdgeKind::Attribute
+                ),
+                (
+                    DependencyPath(vec![String::from("tokio"), String::from("main")]),
+                    EdgeKind::Attribute
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_path_attributes() {
+        let text = "#[cfg(unix)]\n#[path = \"sys/unix.rs\"]\npub mod imp;\n#[path=\"other.rs\"] mod other;";
+        let result = parse_mod(text);
+        assert_eq!(
+            result,
+            vec![
+                (
+                    String::from("imp"),
+                    Some(String::from("sys/unix.rs")),
+                    false
+                ),
+                (String::from("other"), Some(String::from("other.rs")), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_tags_test_module_declarations() {
+        let text = "mod foo;\n#[cfg(test)]\nmod tests;";
+        let result = RustParser::parse_module(text, &CfgSet::default()).submodules;
+        assert_eq!(
+            result,
+            vec![
+                ModuleDeclaration {
+                    name: String::from("foo"),
+                    test_only: false,
+                    path: None,
+                    macro_use: false,
+                    disabled: false,
+                    contents: None,
+                },
+                ModuleDeclaration {
+                    name: String::from("tests"),
+                    test_only: true,
+                    path: None,
+                    macro_use: false,
+                    disabled: false,
+                    contents: None,
+                },
             ]
         );
     }
 
     #[test]
-    fn it_keeps_before_cfg_test() {
+    fn it_splits_cfg_test_items() {
         let text = r#"
-foo
+foo;
+#[cfg(test)]
+use bar::{a, b};
 #[cfg(test)]
-bar
+#[allow(unused)]
+fn helper(x: [u8; 2]) {
+    if true {}
+}
+baz;
 #[cfg(test)]
-baz
+mod tests {
+    struct S { a: u8 }
+}
         "#;
-        let result = keep_before_cfg_test(text);
-        assert_eq!(result, Some(String::from("\nfoo\n")));
+        let items = split_cfg_items(text, &CfgSet::default());
+        let lines = |part: &str| {
+            part.lines()
+                .map(str::trim_end)
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        assert_eq!(items.production.len(), text.len());
+        assert_eq!(
+            lines(&items.production),
+            String::from("\nfoo;\n\n\n\n\n\n\n\nbaz;\n\n\n\n\n")
+        );
+        assert_eq!(
+            lines(&items.tests),
+            String::from(
+                "\n\n#[cfg(test)]\nuse bar::{a, b};\n#[cfg(test)]\n#[allow(unused)]\nfn helper(x: [u8; 2]) {\n    if true {}\n}\n\n#[cfg(test)]\nmod tests {\n    struct S { a: u8 }\n}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn it_splits_items_under_compound_test_predicates() {
+        let text = r#"
+#[cfg(all(test, feature = "json"))]
+use mock_json::Server;
+#[cfg(any(test, feature = "yaml"))]
+use mock_yaml::Server;
+#[cfg(not(test))]
+use real::Server;
+"#;
+        let cfgs = CfgSet {
+            features: Some(Set::from([String::from("json")])),
+            ..CfgSet::default()
+        };
+        let result = RustParser::parse_module(text, &cfgs)
+            .dependencies
+            .into_iter()
+            .map(|dependency| (dependency.path.0.join("::"), dependency.test_only))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                (String::from("real::Server"), false),
+                (String::from("mock_json::Server"), true),
+                (String::from("mock_yaml::Server"), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_drops_items_disabled_by_features() {
+        let text = r#"
+#[cfg(feature = "json")]
+use serde_json::Value;
+#[cfg(all(unix, feature = "json"))]
+mod fallback;
+#[cfg(unix)]
+use nix::sys;
+#[cfg(any(feature = "yaml", test))]
+mod yaml;
+"#;
+        let cfgs = CfgSet {
+            features: Some(Set::from([String::from("yaml")])),
+            ..CfgSet::default()
+        };
+        let module = RustParser::parse_module(text, &cfgs);
+        assert_eq!(
+            module.dependencies,
+            vec![Dependency {
+                rooted: true,
+                span: Some(Span {
+                    line: 7,
+                    column: 1,
+                    ..Span::default()
+                }),
+                ..DependencyPath(vec![String::from("nix"), String::from("sys")]).into()
+            }]
+        );
+        assert_eq!(
+            module
+                .submodules
+                .iter()
+                .map(|declaration| (declaration.name.as_str(), declaration.disabled))
+                .collect::<Vec<_>>(),
+            vec![("yaml", false), ("fallback", true)]
+        );
+    }
+
+    #[test]
+    fn it_blanks_comments_and_literals() {
+        let text = r###"a // use x;
+/* b /* nested */ c */ d
+"use y;" r#"use "z";"# br"w" 'q' '\'' '\u{e9}' &'a str f<'b>
+#[path = "sys/unix.rs"] é"é""###;
+        let result = blank_comments_and_literals(text);
+        assert_eq!(result.chars().count(), text.chars().count());
+        assert_eq!(
+            result,
+            String::from(
+                r###"a          
+                       d
+"      " r#"        "# br" " ' ' '  ' '      ' &'a str f<'b>
+#[path = "sys/unix.rs"] é" ""###
+            )
+        );
+    }
+
+    #[test]
+    fn it_ignores_use_in_comments_and_strings() {
+        let text = r###"
+//! ```
+//! use crate::doc::example;
+//! ```
+/// use crate::doc::item;
+use crate::real::one;
+/*
+use crate::commented::out;
+*/
+const SNIPPET: &str = r#"
+use crate::in_raw::string;
+"#;
+const OTHER: &str = "
+use crate::in_string;";
+use crate::real::two; // use crate::trailing::comment;
+        "###;
+        let result = RustParser::parse_module(text, &CfgSet::default())
+            .dependencies
+            .into_iter()
+            .map(|dependency| dependency.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                DependencyPath(vec![
+                    String::from("crate"),
+                    String::from("real"),
+                    String::from("one")
+                ]),
+                DependencyPath(vec![
+                    String::from("crate"),
+                    String::from("real"),
+                    String::from("two")
+                ]),
+            ]
+        );
     }
 
     #[test]
@@ -251,23 +1710,33 @@ use self::foobaz;
 use external::aaa;
 
 fn main() {
-    crate::other::dep::fun(); // not handled
+    crate::other::dep::fun();
 }
 
 #[cfg(test)]
 mod tests {
-    use inside_tests::other; // discarded
+    use inside_tests::other; // test-only
 }
+
+#[cfg(test)]
+use inside_tests::helper; // test-only
+
+use after_tests::kept;
         "#;
-        let mut result = RustParser::parse_dependencies(
-            text,
-            //"cargo_graphmod",
-            //vec![String::from("path"), String::from("mod")],
-        );
+        let (tests, production): (Vec<Dependency>, Vec<Dependency>) =
+            RustParser::parse_module(text, &CfgSet::default())
+                .dependencies
+                .into_iter()
+                .partition(|dependency| dependency.test_only);
+        let mut result = production
+            .into_iter()
+            .map(|dependency| dependency.path)
+            .collect::<Vec<_>>();
         result.sort();
         assert_eq!(
             result,
             vec![
+                DependencyPath(vec![String::from("after_tests"), String::from("kept")]),
                 DependencyPath(vec![
                     String::from("cargo_graphmod"),
                     String::from("dependencies_parser"),
@@ -300,9 +1769,147 @@ mod tests {
                     String::from("dependencies_parser"),
                     String::from("foobar")
                 ]),
+                DependencyPath(vec![
+                    String::from("crate"),
+                    String::from("other"),
+                    String::from("dep"),
+                    String::from("fun")
+                ]),
                 DependencyPath(vec![String::from("external"), String::from("aaa")]),
                 DependencyPath(vec![String::from("self"), String::from("foobaz")]),
             ]
         );
+        let mut result = tests
+            .into_iter()
+            .map(|dependency| dependency.path)
+            .collect::<Vec<_>>();
+        result.sort();
+        assert_eq!(
+            result,
+            vec![DependencyPath(vec![
+                String::from("inside_tests"),
+                String::from("helper")
+            ])]
+        );
+        let tests = &RustParser::parse_module(text, &CfgSet::default()).submodules[0];
+        assert!(tests.test_only);
+        assert_eq!(
+            tests
+                .contents
+                .as_ref()
+                .unwrap()
+                .dependencies
+                .iter()
+                .map(|dependency| (dependency.path.clone(), dependency.test_only))
+                .collect::<Vec<_>>(),
+            vec![(
+                DependencyPath(vec![String::from("inside_tests"), String::from("other")]),
+                true
+            )]
+        );
+    }
+
+    #[test]
+    fn it_parses_qualified_paths_in_expressions_and_types() {
+        let text = r#"
+use crate::storage;
+use std::fmt;
+
+#[derive(serde::Serialize)]
+pub(in crate::web) struct Handler {
+    db: storage::Db,
+}
+
+impl fmt::Display for Handler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let user = super::User::new(Vec::new(), u8::MAX);
+        ::log::info!("{}", crate::helpers::format(&user));
+        Ok(())
+    }
+}
+"#;
+        let result = RustParser::parse_module(text, &CfgSet::default())
+            .dependencies
+            .into_iter()
+            .filter(|dependency| dependency.kind == EdgeKind::Use && dependency.span.is_some())
+            .map(|dependency| {
+                let span = dependency.span.unwrap();
+                (
+                    dependency.path.0.join("::"),
+                    dependency.rooted,
+                    span.line,
+                    span.column,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                (String::from("crate::storage"), true, 2, 1),
+                (String::from("std::fmt"), true, 3, 1),
+                (String::from("crate::storage::Db"), true, 7, 9),
+                (String::from("std::fmt::Formatter"), true, 11, 27),
+                (String::from("std::fmt::Result"), true, 11, 46),
+                (String::from("super::User::new"), false, 12, 20),
+                (String::from("crate::helpers::format"), false, 13, 28),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_inline_modules_with_their_own_scope() {
+        let text = r#"
+use crate::outer;
+
+pub mod inner {
+    use super::x;
+
+    #[cfg(feature = "json")]
+    mod json { use serde_json::Value; }
+}
+
+#[path = "other.rs"]
+mod declared;
+"#;
+        let cfgs = CfgSet {
+            features: Some(Set::new()),
+            ..CfgSet::default()
+        };
+        let module = RustParser::parse_module(text, &cfgs);
+        assert_eq!(
+            module
+                .dependencies
+                .iter()
+                .map(|dependency| dependency.path.0.join("::"))
+                .collect::<Vec<_>>(),
+            vec![String::from("crate::outer")]
+        );
+        assert_eq!(
+            module
+                .submodules
+                .iter()
+                .map(|submodule| (submodule.name.as_str(), submodule.contents.is_some()))
+                .collect::<Vec<_>>(),
+            vec![("declared", false), ("inner", true)]
+        );
+        let inner = module.submodules[1].contents.as_ref().unwrap();
+        assert_eq!(
+            inner.dependencies,
+            vec![Dependency {
+                path: DependencyPath(vec![String::from("super"), String::from("x")]),
+                kind: EdgeKind::Use,
+                rooted: true,
+                span: Some(Span {
+                    line: 5,
+                    column: 5,
+                    ..Span::default()
+                }),
+                ..Dependency::default()
+            }]
+        );
+        let json = &inner.submodules[0];
+        assert_eq!(json.name, "json");
+        assert!(json.disabled);
+        assert!(json.contents.is_none());
     }
 }
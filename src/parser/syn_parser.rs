@@ -0,0 +1,1374 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
+
+use proc_macro2::{TokenStream, TokenTree};
+use syn::{
+    ext::IdentExt,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit::{self, Visit},
+    Attribute, Expr, ExprLit, ExprPath, ExprStruct, Field, Generics, ImplItem, ImplItemType, Item,
+    ItemConst, ItemExternCrate, ItemImpl, ItemMacro, ItemMod, ItemStatic, ItemTrait, ItemType,
+    ItemUse, Lit, Macro, Meta, MetaNameValue, PatStruct, PatTupleStruct, Path, Signature, Token,
+    TraitBound, TraitItem, TraitItemType, Type, TypePath, UseTree, Visibility,
+};
+
+use crate::{
+    cfg::CfgSet,
+    dependencies::{Dependency, DependencyPath, EdgeKind, Span},
+};
+
+use super::{rust_parser::RustParser, MacroDefinition, ModuleDeclaration, ParsedModule, Parser};
+
+const CFG: &str = "cfg";
+const CFG_ATTR: &str = "cfg_attr";
+const DOC: &str = "doc";
+const DERIVE: &str = "derive";
+const PATH: &str = "path";
+const MACRO_RULES: &str = "macro_rules";
+const MACRO_EXPORT: &str = "macro_export";
+const MACRO_USE: &str = "macro_use";
+const GLOB: &str = "*";
+const SELF: &str = "self";
+const UNDERSCORE: &str = "_";
+/** The tools whose attributes, such as `#[rustfmt::skip]`, are not paths. */
+const TOOLS: [&str; 4] = ["clippy", "diagnostic", "rustdoc", "rustfmt"];
+const PRIMITIVE_TYPES: [&str; 17] = [
+    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "str", "u8", "u16",
+    "u32", "u64", "u128", "usize",
+];
+
+/**
+ * Whether a `#[cfg(...)]` attribute compiles the item only for the tests.
+ */
+fn is_cfg_test(attrs: &[Attribute], cfgs: &CfgSet) -> bool {
+    attrs.iter().any(|attr| match &attr.meta {
+        Meta::List(list) if list.path.is_ident(CFG) => cfgs.is_test_only(&list.tokens.to_string()),
+        _ => false,
+    })
+}
+
+/**
+ * Whether a `#[cfg(...)]` attribute compiles the item out.
+ */
+fn is_disabled(attrs: &[Attribute], cfgs: &CfgSet) -> bool {
+    attrs.iter().any(|attr| match &attr.meta {
+        Meta::List(list) if list.path.is_ident(CFG) => cfgs.disables(&list.tokens.to_string()),
+        _ => false,
+    })
+}
+
+/**
+ * The value of a `#[path = "..."]` attribute.
+ */
+fn path_attribute(attrs: &[Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| match &attr.meta {
+        Meta::NameValue(MetaNameValue {
+            path,
+            value:
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(value),
+                    ..
+                }),
+            ..
+        }) if path.is_ident(PATH) => Some(value.value()),
+        _ => None,
+    })
+}
+
+fn has_attribute(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident(name))
+}
+
+/**
+ * The segments of a path without their raw-identifier prefixes.
+ * A leading `::` is dropped, and recorded as `global` by the dependency.
+ */
+fn path_segments(path: &Path) -> Vec<String> {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.unraw().to_string())
+        .collect()
+}
+
+fn span_of(span: proc_macro2::Span) -> Span {
+    let start = span.start();
+    Span {
+        line: start.line,
+        column: start.column + 1,
+        ..Span::default()
+    }
+}
+
+fn item_attributes(item: &Item) -> &[Attribute] {
+    match item {
+        Item::Const(item) => &item.attrs,
+        Item::Enum(item) => &item.attrs,
+        Item::ExternCrate(item) => &item.attrs,
+        Item::Fn(item) => &item.attrs,
+        Item::ForeignMod(item) => &item.attrs,
+        Item::Impl(item) => &item.attrs,
+        Item::Macro(item) => &item.attrs,
+        Item::Mod(item) => &item.attrs,
+        Item::Static(item) => &item.attrs,
+        Item::Struct(item) => &item.attrs,
+        Item::Trait(item) => &item.attrs,
+        Item::TraitAlias(item) => &item.attrs,
+        Item::Type(item) => &item.attrs,
+        Item::Union(item) => &item.attrs,
+        Item::Use(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+/**
+ * The name of the top-level item the dependencies of an item belong to.
+ */
+fn item_name(item: &Item) -> Option<String> {
+    let ident = match item {
+        Item::Const(item) => &item.ident,
+        Item::Enum(item) => &item.ident,
+        Item::Fn(item) => &item.sig.ident,
+        Item::Static(item) => &item.ident,
+        Item::Struct(item) => &item.ident,
+        Item::Trait(item) => &item.ident,
+        Item::TraitAlias(item) => &item.ident,
+        Item::Type(item) => &item.ident,
+        Item::Union(item) => &item.ident,
+        Item::Impl(item) => match item.self_ty.as_ref() {
+            Type::Path(ty) => &ty.path.segments.last()?.ident,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(ident.unraw().to_string())
+}
+
+fn impl_item_attributes(item: &ImplItem) -> &[Attribute] {
+    match item {
+        ImplItem::Const(item) => &item.attrs,
+        ImplItem::Fn(item) => &item.attrs,
+        ImplItem::Type(item) => &item.attrs,
+        ImplItem::Macro(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+fn trait_item_attributes(item: &TraitItem) -> &[Attribute] {
+    match item {
+        TraitItem::Const(item) => &item.attrs,
+        TraitItem::Fn(item) => &item.attrs,
+        TraitItem::Type(item) => &item.attrs,
+        TraitItem::Macro(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+/**
+ * Whether a path such as `foo::bar` may start with a module or a crate,
+ * rather than with a type (`Vec::new`, `Self::new`, `u8::MAX`).
+ */
+fn may_start_with_module(path: &[String]) -> bool {
+    match path.first() {
+        Some(fst) if path.len() > 1 => {
+            fst.starts_with(|c: char| c.is_lowercase() || c == '_')
+                && !PRIMITIVE_TYPES.contains(&fst.as_str())
+        }
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct DependencyCollector {
+    dependencies: Vec<Dependency>,
+    /** The paths imported by `use` under each name, and whether they start with `::`. */
+    bindings: Map<String, (Vec<String>, bool)>,
+    /** The paths, along with the top-level item they appear in. */
+    paths: Vec<(Option<String>, Dependency)>,
+    submodules: Vec<ModuleDeclaration>,
+    aliases: Map<String, String>,
+    macros: Vec<MacroDefinition>,
+    invocations: Vec<(Option<String>, Dependency)>,
+    /** The names of the items defined by the module, except for `impl` blocks. */
+    defined: Set<String>,
+    items: Map<String, Vec<Dependency>>,
+    /** The top-level item being visited. */
+    item: Option<String>,
+    test_only: bool,
+    reexporting: bool,
+    /** Whether the `use` declaration being visited starts with `::`. */
+    global_use: bool,
+    /** Whether the signature of an item is being visited, rather than its body. */
+    signature: bool,
+    cfgs: CfgSet,
+}
+
+impl DependencyCollector {
+    /**
+     * Enters an item; returns whether the enclosing item was test-only.
+     */
+    fn enter(&mut self, attrs: &[Attribute]) -> bool {
+        let outer = self.test_only;
+        self.test_only |= is_cfg_test(attrs, &self.cfgs);
+        outer
+    }
+
+    /**
+     * A dependency declared by `use` or `extern crate`.
+     */
+    fn add_dependency(&mut self, path: Vec<String>, span: &Span) {
+        let dependency = Dependency {
+            test_only: self.test_only,
+            reexported: self.reexporting,
+            rooted: true,
+            global: self.global_use,
+            span: Some(span.clone()),
+            ..DependencyPath(path).into()
+        };
+        if let Some(item) = &self.item {
+            self.items
+                .entry(item.clone())
+                .or_default()
+                .push(dependency.clone());
+        }
+        self.dependencies.push(dependency);
+    }
+
+    /**
+     * The imports of a `use` declaration are all located at its `use` keyword.
+     */
+    fn develop_use_tree(&mut self, prefix: &mut Vec<String>, tree: &UseTree, span: &Span) {
+        match tree {
+            UseTree::Path(path) => {
+                prefix.push(path.ident.unraw().to_string());
+                self.develop_use_tree(prefix, &path.tree, span);
+                prefix.pop();
+            }
+            UseTree::Name(name) => {
+                // `use foo::{self, bar}` imports the module `foo` itself
+                if name.ident != SELF || prefix.is_empty() {
+                    prefix.push(name.ident.unraw().to_string());
+                    self.add_use(prefix.clone(), name.ident.unraw().to_string(), span);
+                    prefix.pop();
+                } else if let Some(last) = prefix.last() {
+                    self.add_use(prefix.clone(), last.clone(), span);
+                }
+            }
+            UseTree::Rename(rename) => {
+                prefix.push(rename.ident.unraw().to_string());
+                self.add_use(prefix.clone(), rename.rename.unraw().to_string(), span);
+                prefix.pop();
+            }
+            UseTree::Glob(_) => {
+                prefix.push(String::from(GLOB));
+                self.add_dependency(prefix.clone(), span);
+                prefix.pop();
+            }
+            UseTree::Group(group) => {
+                for tree in &group.items {
+                    self.develop_use_tree(prefix, tree, span);
+                }
+            }
+        }
+    }
+
+    fn add_use(&mut self, path: Vec<String>, binding: String, span: &Span) {
+        if binding != UNDERSCORE {
+            self.bindings
+                .insert(binding, (path.clone(), self.global_use));
+        }
+        self.add_dependency(path, span);
+    }
+
+    fn add_path(&mut self, path: &Path) {
+        let kind = if self.signature {
+            EdgeKind::Signature
+        } else {
+            EdgeKind::Use
+        };
+        self.add_path_of_kind(path, kind);
+    }
+
+    fn visit_signature_part(&mut self, visit: impl FnOnce(&mut Self)) {
+        let outer = self.signature;
+        self.signature = true;
+        visit(self);
+        self.signature = outer;
+    }
+
+    fn add_path_of_kind(&mut self, path: &Path, kind: EdgeKind) {
+        self.paths.push((
+            self.item.clone(),
+            Dependency {
+                path: DependencyPath(path_segments(path)),
+                kind,
+                test_only: self.test_only,
+                rooted: path.leading_colon.is_some(),
+                global: path.leading_colon.is_some(),
+                span: Some(span_of(path.span())),
+                ..Dependency::default()
+            },
+        ));
+    }
+
+    /**
+     * The string literals of the arguments of an attribute which are paths, such as `"crate::time_fmt"`.
+     */
+    fn add_paths_in_strings(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) => self.add_paths_in_strings(group.stream()),
+                TokenTree::Literal(literal) => {
+                    if let Lit::Str(value) = Lit::new(literal) {
+                        match value.parse::<Path>() {
+                            Ok(path) if path.segments.len() > 1 => {
+                                self.add_path_of_kind(&path, EdgeKind::Attribute)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /**
+     * Replaces the first segment of a path by the path it is imported from, if any,
+     * telling whether that one starts with `::`. A path starting with `::` is not imported.
+     */
+    fn resolve_binding(&self, dependency: &Dependency) -> Option<(Vec<String>, bool)> {
+        let path = &dependency.path.0;
+        if dependency.global {
+            return None;
+        }
+        let (imported, global) = self.bindings.get(path.first()?)?;
+        Some((
+            imported.iter().chain(&path[1..]).cloned().collect(),
+            *global,
+        ))
+    }
+
+    /**
+     * Resolves the first segment of the collected paths against the names imported by `use`.
+     * A single name used as such in a body is a dependency of the item it appears in only,
+     * as the module depends on it through its `use` declaration already;
+     * named by a signature, it is a signature dependency of the module as well.
+     * Derives and attributes cannot name the items of their own crate, as they are procedural macros.
+     */
+    fn into_module(mut self) -> ParsedModule {
+        for (item, dependency) in std::mem::take(&mut self.paths) {
+            let path = &dependency.path.0;
+            let imported = self.resolve_binding(&dependency);
+            let rooted = dependency.rooted || imported.is_some();
+            let global = dependency.global || imported.as_ref().is_some_and(|(_, global)| *global);
+            let resolved = imported
+                .map(|(resolved, _)| (resolved, path.len() > 1 || dependency.kind != EdgeKind::Use))
+                .or_else(|| {
+                    // an item referring to itself is not a dependency
+                    let local = matches!(
+                        dependency.kind,
+                        EdgeKind::Use | EdgeKind::Signature | EdgeKind::Implements
+                    ) && path.first().is_some_and(|fst| {
+                        self.defined.contains(fst) && item.as_ref() != Some(fst)
+                    });
+                    local.then(|| ([&[String::from(SELF)], path.as_slice()].concat(), false))
+                })
+                .or_else(|| may_start_with_module(path).then(|| (path.clone(), true)));
+            let Some((resolved, for_module)) = resolved else {
+                continue;
+            };
+            let dependency = Dependency {
+                rooted,
+                global,
+                path: DependencyPath(resolved),
+                ..dependency
+            };
+            if let Some(item) = item {
+                self.items.entry(item).or_default().push(dependency.clone());
+            }
+            if for_module {
+                self.dependencies.push(dependency);
+            }
+        }
+        // a macro imported by `use` is named by its binding, otherwise it is looked up later on
+        for (item, mut invocation) in std::mem::take(&mut self.invocations) {
+            if let Some((resolved, global)) = self.resolve_binding(&invocation) {
+                invocation.path = DependencyPath(resolved);
+                invocation.rooted = true;
+                invocation.global = global;
+            }
+            if let Some(item) = item {
+                self.items.entry(item).or_default().push(invocation.clone());
+            }
+            self.dependencies.push(invocation);
+        }
+        // the `impl` blocks of types defined elsewhere are not items of the module
+        let mut items = self
+            .defined
+            .iter()
+            .map(|name| (name.clone(), vec![]))
+            .collect::<Map<_, _>>();
+        for (name, dependencies) in self.items {
+            if let Some(item) = items.get_mut(&name) {
+                item.extend(dependencies);
+            }
+        }
+        ParsedModule {
+            dependencies: self.dependencies,
+            submodules: self.submodules,
+            aliases: self.aliases,
+            macros: self.macros,
+            items,
+            fallback: None,
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for DependencyCollector {
+    fn visit_item(&mut self, item: &'ast Item) {
+        // disabled module declarations are kept, so that their files are not reported as stray
+        if is_disabled(item_attributes(item), &self.cfgs) && !matches!(item, Item::Mod(_)) {
+            return;
+        }
+        let outer = self.enter(item_attributes(item));
+        let outer_item = self.item.clone();
+        if outer_item.is_none() {
+            self.item = item_name(item);
+            if !matches!(item, Item::Impl(_)) {
+                self.defined.extend(self.item.clone());
+            }
+        }
+        visit::visit_item(self, item);
+        self.item = outer_item;
+        self.test_only = outer;
+    }
+
+    /**
+     * The type an `impl` block is for belongs to its signature, unlike the bodies of its functions.
+     */
+    fn visit_item_impl(&mut self, item: &'ast ItemImpl) {
+        for attr in &item.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_generics(&item.generics);
+        if let Some((_, path, _)) = &item.trait_ {
+            self.add_path_of_kind(path, EdgeKind::Implements);
+            self.visit_path(path);
+        }
+        self.visit_signature_part(|collector| collector.visit_type(&item.self_ty));
+        for impl_item in &item.items {
+            self.visit_impl_item(impl_item);
+        }
+    }
+
+    /**
+     * The supertraits of a trait.
+     */
+    fn visit_item_trait(&mut self, item: &'ast ItemTrait) {
+        for attr in &item.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_visibility(&item.vis);
+        self.visit_generics(&item.generics);
+        self.visit_signature_part(|collector| {
+            for bound in &item.supertraits {
+                collector.visit_type_param_bound(bound);
+            }
+        });
+        for trait_item in &item.items {
+            self.visit_trait_item(trait_item);
+        }
+    }
+
+    fn visit_item_const(&mut self, item: &'ast ItemConst) {
+        for attr in &item.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_visibility(&item.vis);
+        self.visit_generics(&item.generics);
+        self.visit_signature_part(|collector| collector.visit_type(&item.ty));
+        self.visit_expr(&item.expr);
+    }
+
+    fn visit_item_static(&mut self, item: &'ast ItemStatic) {
+        for attr in &item.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_visibility(&item.vis);
+        self.visit_signature_part(|collector| collector.visit_type(&item.ty));
+        self.visit_expr(&item.expr);
+    }
+
+    /**
+     * Derives, attribute macros, and the paths given to attributes as strings.
+     */
+    fn visit_attribute(&mut self, attr: &'ast Attribute) {
+        let path = attr.path();
+        if path.is_ident(DERIVE) {
+            if let Ok(paths) = attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+            {
+                for path in &paths {
+                    self.add_path_of_kind(path, EdgeKind::Derives);
+                }
+            }
+            return;
+        }
+        if [CFG, CFG_ATTR, DOC, PATH]
+            .iter()
+            .any(|name| path.is_ident(name))
+        {
+            return;
+        }
+        let tool = path
+            .segments
+            .first()
+            .is_some_and(|segment| TOOLS.contains(&segment.ident.to_string().as_str()));
+        if !tool {
+            self.add_path_of_kind(path, EdgeKind::Attribute);
+        }
+        if let Meta::List(list) = &attr.meta {
+            self.add_paths_in_strings(list.tokens.clone());
+        }
+    }
+
+    fn visit_field(&mut self, field: &'ast Field) {
+        self.visit_signature_part(|collector| visit::visit_field(collector, field));
+    }
+
+    fn visit_signature(&mut self, signature: &'ast Signature) {
+        self.visit_signature_part(|collector| visit::visit_signature(collector, signature));
+    }
+
+    /**
+     * The generic parameters and the where-clause of an item.
+     */
+    fn visit_generics(&mut self, generics: &'ast Generics) {
+        self.visit_signature_part(|collector| visit::visit_generics(collector, generics));
+    }
+
+    fn visit_item_type(&mut self, item: &'ast ItemType) {
+        self.visit_signature_part(|collector| visit::visit_item_type(collector, item));
+    }
+
+    fn visit_impl_item_type(&mut self, item: &'ast ImplItemType) {
+        self.visit_signature_part(|collector| visit::visit_impl_item_type(collector, item));
+    }
+
+    fn visit_trait_item_type(&mut self, item: &'ast TraitItemType) {
+        self.visit_signature_part(|collector| visit::visit_trait_item_type(collector, item));
+    }
+
+    fn visit_impl_item(&mut self, item: &'ast ImplItem) {
+        if is_disabled(impl_item_attributes(item), &self.cfgs) {
+            return;
+        }
+        let outer = self.enter(impl_item_attributes(item));
+        visit::visit_impl_item(self, item);
+        self.test_only = outer;
+    }
+
+    fn visit_trait_item(&mut self, item: &'ast TraitItem) {
+        if is_disabled(trait_item_attributes(item), &self.cfgs) {
+            return;
+        }
+        let outer = self.enter(trait_item_attributes(item));
+        visit::visit_trait_item(self, item);
+        self.test_only = outer;
+    }
+
+    /**
+     * Inline modules are parsed with their own scope.
+     */
+    fn visit_item_mod(&mut self, module: &'ast ItemMod) {
+        let disabled = is_disabled(&module.attrs, &self.cfgs);
+        let contents = module
+            .content
+            .as_ref()
+            .filter(|_| !disabled)
+            .map(|(_, items)| {
+                let mut collector = DependencyCollector {
+                    test_only: self.test_only,
+                    cfgs: self.cfgs.clone(),
+                    ..DependencyCollector::default()
+                };
+                for item in items {
+                    collector.visit_item(item);
+                }
+                collector.into_module()
+            });
+        self.submodules.push(ModuleDeclaration {
+            name: module.ident.unraw().to_string(),
+            test_only: self.test_only,
+            path: path_attribute(&module.attrs),
+            macro_use: has_attribute(&module.attrs, MACRO_USE),
+            disabled,
+            contents,
+        });
+    }
+
+    fn visit_item_macro(&mut self, item: &'ast ItemMacro) {
+        match &item.ident {
+            Some(name) if item.mac.path.is_ident(MACRO_RULES) => {
+                self.macros.push(MacroDefinition {
+                    name: name.unraw().to_string(),
+                    exported: has_attribute(&item.attrs, MACRO_EXPORT),
+                });
+            }
+            _ => visit::visit_item_macro(self, item),
+        }
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        self.invocations.push((
+            self.item.clone(),
+            Dependency {
+                path: DependencyPath(path_segments(&mac.path)),
+                kind: EdgeKind::Macro,
+                test_only: self.test_only,
+                rooted: mac.path.leading_colon.is_some(),
+                global: mac.path.leading_colon.is_some(),
+                span: Some(span_of(mac.path.span())),
+                ..Dependency::default()
+            },
+        ));
+        visit::visit_macro(self, mac);
+    }
+
+    fn visit_item_use(&mut self, item: &'ast ItemUse) {
+        self.reexporting = !matches!(item.vis, Visibility::Inherited);
+        self.global_use = item.leading_colon.is_some();
+        self.develop_use_tree(&mut vec![], &item.tree, &span_of(item.use_token.span));
+        self.reexporting = false;
+        self.global_use = false;
+    }
+
+    fn visit_item_extern_crate(&mut self, item: &'ast ItemExternCrate) {
+        let name = item.ident.unraw().to_string();
+        if name == SELF {
+            return;
+        }
+        if let Some((_, alias)) = &item.rename {
+            if alias != UNDERSCORE {
+                self.aliases.insert(alias.unraw().to_string(), name.clone());
+            }
+        }
+        self.add_dependency(vec![name], &span_of(item.extern_token.span));
+    }
+
+    fn visit_expr_path(&mut self, expr: &'ast ExprPath) {
+        self.add_path(&expr.path);
+        visit::visit_expr_path(self, expr);
+    }
+
+    fn visit_expr_struct(&mut self, expr: &'ast ExprStruct) {
+        self.add_path(&expr.path);
+        visit::visit_expr_struct(self, expr);
+    }
+
+    fn visit_pat_struct(&mut self, pat: &'ast PatStruct) {
+        self.add_path(&pat.path);
+        visit::visit_pat_struct(self, pat);
+    }
+
+    fn visit_pat_tuple_struct(&mut self, pat: &'ast PatTupleStruct) {
+        self.add_path(&pat.path);
+        visit::visit_pat_tuple_struct(self, pat);
+    }
+
+    fn visit_type_path(&mut self, ty: &'ast TypePath) {
+        self.add_path(&ty.path);
+        visit::visit_type_path(self, ty);
+    }
+
+    fn visit_trait_bound(&mut self, bound: &'ast TraitBound) {
+        self.add_path(&bound.path);
+        visit::visit_trait_bound(self, bound);
+    }
+}
+
+/**
+ * Parses the syntax tree of a file; falls back to the regex parser when the file is not valid Rust.
+ */
+pub struct SynParser {}
+
+impl Parser for SynParser {
+    fn parse_module(file_contents: &str, cfgs: &CfgSet) -> ParsedModule {
+        match syn::parse_file(file_contents) {
+            Ok(file) => {
+                let mut collector = DependencyCollector {
+                    cfgs: cfgs.clone(),
+                    ..DependencyCollector::default()
+                };
+                collector.visit_file(&file);
+                collector.into_module()
+            }
+            Err(error) => ParsedModule {
+                fallback: Some(error.to_string()),
+                ..RustParser::parse_module(file_contents, cfgs)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap as Map, BTreeSet as Set};
+
+    use crate::{
+        cfg::CfgSet,
+        dependencies::{Dependency, DependencyPath, EdgeKind, Span},
+        parser::{syn_parser::SynParser, MacroDefinition, ModuleDeclaration, ParsedModule, Parser},
+    };
+
+    fn make_path(components: &[&str]) -> DependencyPath {
+        DependencyPath(components.iter().map(|s| s.to_string()).collect())
+    }
+
+    fn located(components: &[&str], line: usize, column: usize) -> Dependency {
+        Dependency {
+            span: Some(Span {
+                line,
+                column,
+                ..Span::default()
+            }),
+            ..make_path(components).into()
+        }
+    }
+
+    /**
+     * A dependency declared by `use` or `extern crate`.
+     */
+    fn declared(components: &[&str], line: usize, column: usize) -> Dependency {
+        Dependency {
+            rooted: true,
+            ..located(components, line, column)
+        }
+    }
+
+    fn parse_paths(text: &str, test_only: bool) -> Vec<DependencyPath> {
+        let mut result = SynParser::parse_module(text, &CfgSet::default())
+            .dependencies
+            .into_iter()
+            .filter(|dependency| dependency.test_only == test_only)
+            .map(|dependency| dependency.path)
+            .collect::<Vec<_>>();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn it_develops_nested_groups() {
+        let text = "use foo::{bar1, bar2 as b, bar3::{self, far, boo::*}};";
+        let result = parse_paths(text, false);
+        assert_eq!(
+            result,
+            vec![
+                make_path(&["foo", "bar1"]),
+                make_path(&["foo", "bar2"]),
+                make_path(&["foo", "bar3"]),
+                make_path(&["foo", "bar3", "boo"]),
+                make_path(&["foo", "bar3", "far"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_flags_glob_imports_and_reexports() {
+        let text =
+            "pub use self::inner::*;\nuse other::*;\npub(crate) use crate::Thing;\nuse std::fmt;";
+        let result = SynParser::parse_module(text, &CfgSet::default())
+            .dependencies
+            .into_iter()
+            .map(|dependency| (dependency.path, dependency.glob, dependency.reexported))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                (make_path(&["self", "inner"]), true, true),
+                (make_path(&["other"]), true, false),
+                (make_path(&["crate", "Thing"]), false, true),
+                (make_path(&["std", "fmt"]), false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_ignores_comments_and_attributes() {
+        let text = r#"
+// use commented::out;
+/* use block::{comment}; */
+#[allow(unused_imports)]
+pub(crate) use
+    crate::{ // a comment
+        foo::bar, /* another one */ baz,
+    };
+        "#;
+        let result = parse_paths(text, false);
+        assert_eq!(
+            result,
+            vec![
+                make_path(&["crate", "baz"]),
+                make_path(&["crate", "foo", "bar"])
+            ]
+        );
+    }
+
+    #[test]
+    fn it_tags_dependencies_under_compound_test_predicates() {
+        let text = r#"
+#[cfg(all(test, feature = "json"))]
+use mock_json::Server;
+#[cfg(any(test, feature = "yaml"))]
+use mock_yaml::Server;
+#[cfg(not(test))]
+use real::Server;
+        "#;
+        let cfgs = CfgSet {
+            features: Some(Set::from([String::from("json")])),
+            ..CfgSet::default()
+        };
+        let mut result = SynParser::parse_module(text, &cfgs)
+            .dependencies
+            .into_iter()
+            .map(|dependency| (dependency.path, dependency.test_only))
+            .collect::<Vec<_>>();
+        result.sort();
+        assert_eq!(
+            result,
+            vec![
+                (make_path(&["mock_json", "Server"]), true),
+                (make_path(&["mock_yaml", "Server"]), true),
+                (make_path(&["real", "Server"]), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_tags_dependencies_inside_tests() {
+        let text = r#"
+use crate::dependencies_parser::bar as bar;
+
+fn main() {
+    use self::inner::thing;
+}
+
+#[cfg(test)]
+fn helper() {
+    use inside_helper::other;
+}
+
+impl Foo {
+    #[cfg(test)]
+    fn helper() {
+        use inside_method::other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use inside_tests::other;
+}
+
+use after_tests::kept;
+        "#;
+        let result = parse_paths(text, false);
+        assert_eq!(
+            result,
+            vec![
+                make_path(&["after_tests", "kept"]),
+                make_path(&["crate", "dependencies_parser", "bar"]),
+                make_path(&["self", "inner", "thing"]),
+            ]
+        );
+        let result = parse_paths(text, true);
+        assert_eq!(
+            result,
+            vec![
+                make_path(&["inside_helper", "other"]),
+                make_path(&["inside_method", "other"]),
+            ]
+        );
+        let tests = &SynParser::parse_module(text, &CfgSet::default()).submodules[0];
+        assert!(tests.test_only);
+        assert_eq!(
+            tests.contents.as_ref().unwrap().dependencies,
+            vec![Dependency {
+                test_only: true,
+                ..declared(&["inside_tests", "other"], 22, 5)
+            }]
+        );
+    }
+
+    #[test]
+    fn it_parses_qualified_paths() {
+        let text = r#"
+use crate::storage;
+use std::collections as coll;
+
+fn main() {
+    crate::other::dep::fun();
+    super::Thing::new();
+    let map = coll::BTreeMap::<u8, u8>::new();
+    let db = storage::Db { inner: Vec::new(), max: u8::MAX };
+    if let self::Wrapper::Some(x) = value {}
+}
+
+fn run<T: super::super::Runner>(x: Box<dyn crate::ports::Port>) -> String {
+    String::from("")
+}
+        "#;
+        let mut result = parse_paths(text, false);
+        result.dedup();
+        assert_eq!(
+            result,
+            vec![
+                make_path(&["crate", "other", "dep", "fun"]),
+                make_path(&["crate", "ports", "Port"]),
+                make_path(&["crate", "storage"]),
+                make_path(&["crate", "storage", "Db"]),
+                make_path(&["self", "Wrapper", "Some"]),
+                make_path(&["std", "collections"]),
+                make_path(&["std", "collections", "BTreeMap", "new"]),
+                make_path(&["super", "Thing", "new"]),
+                make_path(&["super", "super", "Runner"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_extern_crates() {
+        let text = r#"
+extern crate serde_json as json;
+extern crate regex;
+extern crate self as my_crate;
+extern crate std as _;
+        "#;
+        let module = SynParser::parse_module(text, &CfgSet::default());
+        assert_eq!(
+            module.dependencies,
+            vec![
+                declared(&["serde_json"], 2, 1),
+                declared(&["regex"], 3, 1),
+                declared(&["std"], 5, 1),
+            ]
+        );
+        assert_eq!(
+            module.aliases,
+            Map::from([(String::from("json"), String::from("serde_json"))])
+        );
+    }
+
+    #[test]
+    fn it_parses_macros() {
+        let text = r#"
+use crate::util::check;
+#[macro_export]
+macro_rules! log_event {
+    ($e:expr) => { println!("{}", $e) };
+}
+#[macro_use]
+mod macros;
+fn f() {
+    log_event!(1);
+    check!(2);
+    crate::util::assert_valid!(3);
+}
+        "#;
+        let module = SynParser::parse_module(text, &CfgSet::default());
+        assert_eq!(
+            module.macros,
+            vec![MacroDefinition {
+                name: String::from("log_event"),
+                exported: true
+            }]
+        );
+        assert!(module.submodules[0].macro_use);
+        let invocations = module
+            .dependencies
+            .into_iter()
+            .filter(|dependency| dependency.kind == EdgeKind::Macro)
+            .map(|dependency| dependency.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            invocations,
+            vec![
+                make_path(&["log_event"]),
+                make_path(&["crate", "util", "check"]),
+                make_path(&["crate", "util", "assert_valid"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_drops_items_disabled_by_features() {
+        let text = r#"
+#[cfg(feature = "json")]
+use serde_json::Value;
+#[cfg(not(feature = "json"))]
+use std::fmt;
+#[cfg(feature = "json")]
+mod json;
+struct S;
+impl S {
+    #[cfg(feature = "json")]
+    fn f() -> serde_json::Value {
+        todo!()
+    }
+}
+"#;
+        let cfgs = CfgSet {
+            features: Some(Default::default()),
+            ..CfgSet::default()
+        };
+        let module = SynParser::parse_module(text, &cfgs);
+        assert_eq!(module.dependencies, vec![declared(&["std", "fmt"], 5, 1)]);
+        assert!(module.submodules[0].disabled);
+    }
+
+    #[test]
+    fn it_collects_the_dependencies_of_items() {
+        let text = r#"
+use crate::domain::Domain;
+use std::fmt;
+pub struct Report {
+    domain: Domain,
+}
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = render(self);
+        write!(f, "{}", text)
+    }
+}
+fn render(report: &Report) -> String {
+    String::new()
+}
+impl Vec<u8> {}
+        "#;
+        let items = SynParser::parse_module(text, &CfgSet::default()).items;
+        let paths = |name: &str| {
+            items[name]
+                .iter()
+                .map(|dependency| dependency.path.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(items.keys().collect::<Vec<_>>(), vec!["Report", "render"]);
+        assert_eq!(
+            paths("Report"),
+            vec![
+                make_path(&["crate", "domain", "Domain"]),
+                make_path(&["std", "fmt", "Display"]),
+                make_path(&["std", "fmt", "Formatter"]),
+                make_path(&["std", "fmt", "Result"]),
+                make_path(&["self", "render"]),
+                make_path(&["write"]),
+            ]
+        );
+        assert_eq!(paths("render"), vec![make_path(&["self", "Report"])]);
+    }
+
+    #[test]
+    fn it_normalizes_absolute_paths_and_raw_identifiers() {
+        let text = r#"
+use ::serde::Serialize;
+use crate::r#type::{r#async, Kind as r#Match};
+mod r#type;
+fn f(x: r#Match) -> ::std::string::String {
+    r#type::r#fn()
+}
+        "#;
+        let module = SynParser::parse_module(text, &CfgSet::default());
+        let mut global = module
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.global)
+            .map(|dependency| dependency.path.clone())
+            .collect::<Vec<_>>();
+        global.sort();
+        assert_eq!(
+            global,
+            vec![
+                make_path(&["serde", "Serialize"]),
+                make_path(&["std", "string", "String"]),
+            ]
+        );
+        let mut result = module
+            .dependencies
+            .into_iter()
+            .map(|dependency| dependency.path)
+            .collect::<Vec<_>>();
+        result.sort();
+        assert_eq!(
+            result,
+            vec![
+                make_path(&["crate", "type", "Kind"]),
+                make_path(&["crate", "type", "Kind"]),
+                make_path(&["crate", "type", "async"]),
+                make_path(&["serde", "Serialize"]),
+                make_path(&["std", "string", "String"]),
+                make_path(&["type", "fn"]),
+            ]
+        );
+        assert_eq!(module.submodules[0].name, "type");
+        assert_eq!(
+            module.items["f"][0].path,
+            make_path(&["crate", "type", "Kind"])
+        );
+    }
+
+    #[test]
+    fn it_parses_trait_impls_derives_and_attributes() {
+        let text = r#"
+use crate::ports::Storage;
+#[derive(Debug, crate::macros::Builder)]
+#[serde(with = "crate::time_fmt")]
+struct Db;
+impl Storage for Db {}
+#[rustfmt::skip]
+#[tokio::main]
+async fn main() {}
+        "#;
+        let module = SynParser::parse_module(text, &CfgSet::default());
+        let mut result = module
+            .dependencies
+            .into_iter()
+            .filter(|dependency| dependency.kind != EdgeKind::Use)
+            .map(|dependency| (dependency.path, dependency.kind))
+            .collect::<Vec<_>>();
+        result.sort();
+        assert_eq!(
+            result,
+            vec![
+                (
+                    make_path(&["crate", "macros", "Builder"]),
+                    EdgeKind::Derives
+                ),
+                (
+                    make_path(&["crate", "ports", "Storage"]),
+                    EdgeKind::Implements
+                ),
+                (make_path(&["crate", "time_fmt"]), EdgeKind::Attribute),
+                (make_path(&["tokio", "main"]), EdgeKind::Attribute),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_tags_the_imported_names_of_signatures_at_module_granularity() {
+        let text = r#"
+use crate::model::User;
+use crate::model::Role;
+fn greet(user: User) -> String {
+    let role: Option<Role> = None;
+    format!("{:?}", role)
+}
+        "#;
+        let module = SynParser::parse_module(text, &CfgSet::default());
+        let signatures = module
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.kind == EdgeKind::Signature)
+            .map(|dependency| dependency.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(signatures, vec![make_path(&["crate", "model", "User"])]);
+    }
+
+    #[test]
+    fn it_tags_the_paths_of_signatures() {
+        let text = r#"
+use crate::ports::Storage;
+struct App {
+    storage: Box<dyn crate::ports::Storage>,
+    kind: Kind,
+}
+enum Kind {
+    Named(super::names::Name),
+}
+fn order<T>(app: &App) -> super::domain::Order
+where
+    T: crate::ports::Clock,
+{
+    crate::domain::Order::new(app.kind)
+}
+impl Iterator for App {
+    type Item = crate::domain::Order;
+}
+trait Repository: crate::ports::Storage {
+    fn save(&self) {
+        crate::ports::flush();
+    }
+}
+const ORIGIN: crate::geometry::Point = crate::geometry::Point::ORIGIN;
+static CLOCK: super::time::Clock = super::time::Clock::new();
+impl crate::domain::Order {}
+        "#;
+        let module = SynParser::parse_module(text, &CfgSet::default());
+        let signatures = |item: &str| {
+            module.items[item]
+                .iter()
+                .filter(|dependency| dependency.kind == EdgeKind::Signature)
+                .map(|dependency| dependency.path.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            signatures("App"),
+            vec![
+                make_path(&["crate", "ports", "Storage"]),
+                make_path(&["self", "Kind"]),
+                make_path(&["crate", "domain", "Order"]),
+            ]
+        );
+        assert_eq!(
+            signatures("Kind"),
+            vec![make_path(&["super", "names", "Name"])]
+        );
+        assert_eq!(
+            signatures("order"),
+            vec![
+                make_path(&["crate", "ports", "Clock"]),
+                make_path(&["self", "App"]),
+                make_path(&["super", "domain", "Order"]),
+            ]
+        );
+        assert_eq!(
+            signatures("Repository"),
+            vec![make_path(&["crate", "ports", "Storage"])]
+        );
+        assert_eq!(
+            signatures("ORIGIN"),
+            vec![make_path(&["crate", "geometry", "Point"])]
+        );
+        assert_eq!(
+            signatures("CLOCK"),
+            vec![make_path(&["super", "time", "Clock"])]
+        );
+        assert!(module.dependencies.iter().any(|dependency| {
+            dependency.kind == EdgeKind::Signature
+                && dependency.path == make_path(&["crate", "domain", "Order"])
+        }));
+        let mut body = module
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.kind == EdgeKind::Use)
+            .map(|dependency| dependency.path.clone())
+            .collect::<Vec<_>>();
+        body.sort();
+        assert_eq!(
+            body,
+            vec![
+                make_path(&["crate", "domain", "Order", "new"]),
+                make_path(&["crate", "geometry", "Point", "ORIGIN"]),
+                make_path(&["crate", "ports", "Storage"]),
+                make_path(&["crate", "ports", "flush"]),
+                make_path(&["super", "time", "Clock", "new"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_module_declarations() {
+        let text = r#"
+pub mod foo;
+#[path = "sys/bar.rs"]
+pub(crate) mod bar;
+mod inline {
+    mod nested;
+}
+#[cfg(test)]
+mod tests;
+        "#;
+        let result = SynParser::parse_module(text, &CfgSet::default()).submodules;
+        assert_eq!(
+            result,
+            vec![
+                ModuleDeclaration {
+                    name: String::from("foo"),
+                    test_only: false,
+                    path: None,
+                    macro_use: false,
+                    disabled: false,
+                    contents: None,
+                },
+                ModuleDeclaration {
+                    name: String::from("bar"),
+                    test_only: false,
+                    path: Some(String::from("sys/bar.rs")),
+                    macro_use: false,
+                    disabled: false,
+                    contents: None,
+                },
+                ModuleDeclaration {
+                    name: String::from("inline"),
+                    test_only: false,
+                    path: None,
+                    macro_use: false,
+                    disabled: false,
+                    contents: Some(ParsedModule {
+                        submodules: vec![ModuleDeclaration {
+                            name: String::from("nested"),
+                            test_only: false,
+                            path: None,
+                            macro_use: false,
+                            disabled: false,
+                            contents: None,
+                        }],
+                        ..ParsedModule::default()
+                    }),
+                },
+                ModuleDeclaration {
+                    name: String::from("tests"),
+                    test_only: true,
+                    path: None,
+                    macro_use: false,
+                    disabled: false,
+                    contents: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_inline_modules_with_their_own_scope() {
+        let text = r#"
+use crate::outer;
+
+mod inner {
+    use super::sibling;
+
+    fn f() {
+        outer::g();
+    }
+}
+        "#;
+        let module = SynParser::parse_module(text, &CfgSet::default());
+        assert_eq!(
+            module.dependencies,
+            vec![declared(&["crate", "outer"], 2, 1)]
+        );
+        let inner = module.submodules[0].contents.as_ref().unwrap();
+        assert_eq!(
+            inner.dependencies,
+            vec![
+                declared(&["super", "sibling"], 5, 5),
+                located(&["outer", "g"], 8, 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_falls_back_to_regexes() {
+        let text = "use foo::bar;\nfn broken( {";
+        let result = parse_paths(text, false);
+        assert_eq!(result, vec![make_path(&["foo", "bar"])]);
+        assert!(SynParser::parse_module(text, &CfgSet::default())
+            .fallback
+            .is_some());
+        assert!(SynParser::parse_module("use foo::bar;", &CfgSet::default())
+            .fallback
+            .is_none());
+    }
+}
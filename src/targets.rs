@@ -0,0 +1,230 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+/**
+ * The cfgs of a target, as printed by `rustc --print cfg --target <triple>`.
+ * They are tabulated here, so that no toolchain is needed for the target.
+ */
+struct Target {
+    triple: &'static str,
+    arch: &'static str,
+    os: &'static str,
+    /** Empty for bare-metal targets. */
+    family: &'static str,
+    env: &'static str,
+    vendor: &'static str,
+    pointer_width: &'static str,
+    endian: &'static str,
+}
+
+const TARGETS: [Target; 18] = [
+    Target {
+        triple: "x86_64-unknown-linux-gnu",
+        arch: "x86_64",
+        os: "linux",
+        family: "unix",
+        env: "gnu",
+        vendor: "unknown",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "x86_64-unknown-linux-musl",
+        arch: "x86_64",
+        os: "linux",
+        family: "unix",
+        env: "musl",
+        vendor: "unknown",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "i686-unknown-linux-gnu",
+        arch: "x86",
+        os: "linux",
+        family: "unix",
+        env: "gnu",
+        vendor: "unknown",
+        pointer_width: "32",
+        endian: "little",
+    },
+    Target {
+        triple: "aarch64-unknown-linux-gnu",
+        arch: "aarch64",
+        os: "linux",
+        family: "unix",
+        env: "gnu",
+        vendor: "unknown",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "armv7-unknown-linux-gnueabihf",
+        arch: "arm",
+        os: "linux",
+        family: "unix",
+        env: "gnu",
+        vendor: "unknown",
+        pointer_width: "32",
+        endian: "little",
+    },
+    Target {
+        triple: "riscv64gc-unknown-linux-gnu",
+        arch: "riscv64",
+        os: "linux",
+        family: "unix",
+        env: "gnu",
+        vendor: "unknown",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "aarch64-linux-android",
+        arch: "aarch64",
+        os: "android",
+        family: "unix",
+        env: "",
+        vendor: "unknown",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "x86_64-unknown-freebsd",
+        arch: "x86_64",
+        os: "freebsd",
+        family: "unix",
+        env: "",
+        vendor: "unknown",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "x86_64-apple-darwin",
+        arch: "x86_64",
+        os: "macos",
+        family: "unix",
+        env: "",
+        vendor: "apple",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "aarch64-apple-darwin",
+        arch: "aarch64",
+        os: "macos",
+        family: "unix",
+        env: "",
+        vendor: "apple",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "aarch64-apple-ios",
+        arch: "aarch64",
+        os: "ios",
+        family: "unix",
+        env: "",
+        vendor: "apple",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "x86_64-pc-windows-msvc",
+        arch: "x86_64",
+        os: "windows",
+        family: "windows",
+        env: "msvc",
+        vendor: "pc",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "x86_64-pc-windows-gnu",
+        arch: "x86_64",
+        os: "windows",
+        family: "windows",
+        env: "gnu",
+        vendor: "pc",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "i686-pc-windows-msvc",
+        arch: "x86",
+        os: "windows",
+        family: "windows",
+        env: "msvc",
+        vendor: "pc",
+        pointer_width: "32",
+        endian: "little",
+    },
+    Target {
+        triple: "aarch64-pc-windows-msvc",
+        arch: "aarch64",
+        os: "windows",
+        family: "windows",
+        env: "msvc",
+        vendor: "pc",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "wasm32-unknown-unknown",
+        arch: "wasm32",
+        os: "unknown",
+        family: "wasm",
+        env: "",
+        vendor: "unknown",
+        pointer_width: "32",
+        endian: "little",
+    },
+    Target {
+        triple: "wasm32-wasip1",
+        arch: "wasm32",
+        os: "wasi",
+        family: "wasm",
+        env: "p1",
+        vendor: "unknown",
+        pointer_width: "32",
+        endian: "little",
+    },
+    Target {
+        triple: "thumbv7em-none-eabihf",
+        arch: "arm",
+        os: "none",
+        family: "",
+        env: "",
+        vendor: "unknown",
+        pointer_width: "32",
+        endian: "little",
+    },
+];
+
+/**
+ * The key/value cfgs of a known target. The `unix` and `windows` names follow from `target_family`.
+ */
+pub fn target_cfgs(triple: &str) -> Option<Vec<(&'static str, &'static str)>> {
+    TARGETS
+        .iter()
+        .find(|target| target.triple == triple)
+        .map(|target| {
+            let mut cfgs = vec![
+                ("target_arch", target.arch),
+                ("target_os", target.os),
+                ("target_env", target.env),
+                ("target_vendor", target.vendor),
+                ("target_pointer_width", target.pointer_width),
+                ("target_endian", target.endian),
+            ];
+            if !target.family.is_empty() {
+                cfgs.push(("target_family", target.family));
+            }
+            cfgs
+        })
+}
+
+pub fn known_targets() -> Vec<&'static str> {
+    TARGETS.iter().map(|target| target.triple).collect()
+}
@@ -44,16 +44,46 @@ impl<K: Eq + Ord + Clone, V: Clone> Trie<K, V> {
         }
     }
 
+    pub fn for_each_value_mut<F: FnMut(&mut V)>(&mut self, f: &mut F) {
+        if let Some(value) = self.value.as_mut() {
+            f(value);
+        }
+        for child in self.children.values_mut() {
+            child.for_each_value_mut(f);
+        }
+    }
+
+    /**
+     * Visits the values along with their keys.
+     */
+    pub fn for_each_entry<F: FnMut(&[K], &V)>(&self, f: &mut F) {
+        self.for_each_entry_from(&mut vec![], f);
+    }
+
+    fn for_each_entry_from<F: FnMut(&[K], &V)>(&self, prefix: &mut Vec<K>, f: &mut F) {
+        if let Some(value) = self.value.as_ref() {
+            f(prefix, value);
+        }
+        for (k, child) in self.children.iter() {
+            prefix.push(k.clone());
+            child.for_each_entry_from(prefix, f);
+            prefix.pop();
+        }
+    }
+
+    pub fn get(&self, k: &[K]) -> Option<&Self> {
+        match k.split_first() {
+            None => Some(self),
+            Some((elt, rest)) => self.children.get(elt).and_then(|child| child.get(rest)),
+        }
+    }
+
     pub fn get_longest_prefix<'b>(&self, k: &'b [K]) -> (&'b [K], NodeKind) {
-        let n = k.len();
         let mut bound = 0;
         let mut trie = self;
-        while let Some(child) = trie.children.get(&k[bound]) {
+        while let Some(child) = k.get(bound).and_then(|elt| trie.children.get(elt)) {
             trie = child;
             bound += 1;
-            if bound == n {
-                break;
-            }
         }
         let kind = if bound == 0 {
             NodeKind::None
@@ -135,6 +165,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_updates_each_value() {
+        let mut trie = Trie::new();
+        trie.insert(VecDeque::from([1]), 10);
+        trie.insert(VecDeque::from([1, 2]), 20);
+        trie.insert(VecDeque::from([3]), 30);
+        trie.for_each_value_mut(&mut |value| *value += 1);
+        let mut expected = Trie::new();
+        expected.insert(VecDeque::from([1]), 11);
+        expected.insert(VecDeque::from([1, 2]), 21);
+        expected.insert(VecDeque::from([3]), 31);
+        assert_eq!(trie, expected);
+    }
+
+    #[test]
+    fn it_visits_each_entry() {
+        let mut trie = Trie::new();
+        trie.insert(VecDeque::from([1]), 10);
+        trie.insert(VecDeque::from([1, 2]), 20);
+        trie.insert(VecDeque::from([3]), 30);
+        let mut entries = vec![];
+        trie.for_each_entry(&mut |k, v| entries.push((k.to_vec(), *v)));
+        assert_eq!(
+            entries,
+            vec![(vec![1], 10), (vec![1, 2], 20), (vec![3], 30)]
+        );
+    }
+
+    #[test]
+    fn it_gets_a_subtrie() {
+        let mut trie = Trie::new();
+        trie.insert(VecDeque::from([1, 2]), 20);
+        assert_eq!(trie.get(&[1, 2]).and_then(|t| t.value), Some(20));
+        assert_eq!(trie.get(&[1]).map(|t| t.children.len()), Some(1));
+        assert_eq!(trie.get(&[2]), None);
+    }
+
     #[test]
     fn it_computes_the_longest_prefix() {
         let mut trie = Trie::new();
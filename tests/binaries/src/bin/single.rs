@@ -0,0 +1,7 @@
+mod util;
+
+use self::util::check;
+
+fn main() {
+    check();
+}
@@ -0,0 +1,8 @@
+mod helper;
+
+use crate::helper::help;
+
+fn main() {
+    binaries::config::load();
+    help();
+}
@@ -0,0 +1,5 @@
+use crate::tokens::Token;
+
+pub fn parse(input: &str) -> Vec<Token> {
+    input.split_whitespace().map(Token::new).collect()
+}
@@ -0,0 +1,7 @@
+pub struct Token(String);
+
+impl Token {
+    pub fn new(text: &str) -> Self {
+        Token(text.to_string())
+    }
+}
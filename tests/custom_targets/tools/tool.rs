@@ -0,0 +1,5 @@
+use custom_targets::parser::parse;
+
+fn main() {
+    println!("{}", parse("a b c").len());
+}
@@ -6,11 +6,11 @@
 
 use std::fs::read_to_string;
 
-use cargo_graphmod::app_builder::run_app;
+use cargo_graphmod::{app_builder::run_app, configuration::Configuration};
 
 #[test]
 fn it_generates_the_graphmod_graph() {
-    let output = run_app("src", "cargo_graphmod");
+    let output = run_app("src", "cargo_graphmod", &Configuration::default());
     let golden_master = read_to_string("modules.dot").unwrap();
     assert_eq!(output.trim(), golden_master.trim());
 }
\ No newline at end of file
@@ -0,0 +1,3 @@
+pub fn check(enabled: bool) {
+    crate::check_config!(enabled);
+}
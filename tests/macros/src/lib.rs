@@ -0,0 +1,4 @@
+#[macro_use]
+mod macros;
+mod configuration;
+mod storage;
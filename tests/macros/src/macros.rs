@@ -0,0 +1,12 @@
+macro_rules! log_event {
+    ($event:expr) => {
+        println!("{}", $event)
+    };
+}
+
+#[macro_export]
+macro_rules! check_config {
+    ($condition:expr) => {
+        assert!($condition)
+    };
+}
@@ -0,0 +1,3 @@
+pub fn store() {
+    log_event!("store");
+}
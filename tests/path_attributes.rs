@@ -0,0 +1,19 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+use std::fs::read_to_string;
+
+use cargo_graphmod::{app_builder::run_app, configuration::Configuration};
+
+#[test]
+fn it_generates_the_graph_of_modules_loaded_through_path_attributes() {
+    let output = run_app(
+        "tests/path_attributes/src",
+        "path_attributes",
+        &Configuration::default(),
+    );
+    let golden_master = read_to_string("tests/path_attributes/modules.dot").unwrap();
+    assert_eq!(output.trim(), golden_master.trim());
+}
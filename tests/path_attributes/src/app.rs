@@ -0,0 +1,6 @@
+use crate::storage::memory_db::MemoryDb;
+use crate::storage::Storage;
+
+pub fn run() {
+    MemoryDb {}.store();
+}
@@ -0,0 +1,7 @@
+use super::Storage;
+
+pub struct MemoryDb {}
+
+impl Storage for MemoryDb {
+    fn store(&self) {}
+}
@@ -0,0 +1,6 @@
+pub trait Storage {
+    fn store(&self);
+}
+
+#[path = "in_memory/db.rs"]
+pub mod memory_db;
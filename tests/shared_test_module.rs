@@ -0,0 +1,26 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+use std::fs::read_to_string;
+
+use cargo_graphmod::{
+    app_builder::run_app,
+    configuration::{Configuration, TargetSelection},
+};
+
+#[test]
+fn it_draws_a_module_shared_by_several_tests_in_each_one() {
+    let configuration = Configuration {
+        targets: Some("lib,test".parse::<TargetSelection>().unwrap()),
+        ..Configuration::default()
+    };
+    let output = run_app(
+        "tests/shared_test_module/src",
+        "shared_test_module",
+        &configuration,
+    );
+    let golden_master = read_to_string("tests/shared_test_module/modules.dot").unwrap();
+    assert_eq!(output.trim(), golden_master.trim());
+}
@@ -0,0 +1,9 @@
+mod common;
+
+use crate::common::setup;
+
+#[test]
+fn it_runs() {
+    setup();
+    shared_test_module::run();
+}
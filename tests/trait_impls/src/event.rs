@@ -0,0 +1,9 @@
+#[derive(Debug, serde::Serialize)]
+pub struct Event {
+    #[serde(serialize_with = "crate::time_fmt::serialize")]
+    time: u64,
+}
+
+impl crate::ports::Publisher for Event {
+    fn publish(&self) {}
+}
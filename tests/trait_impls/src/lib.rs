@@ -0,0 +1,3 @@
+mod event;
+mod ports;
+mod time_fmt;
@@ -0,0 +1,3 @@
+pub trait Publisher {
+    fn publish(&self);
+}
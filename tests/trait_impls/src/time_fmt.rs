@@ -0,0 +1,3 @@
+pub fn serialize<S: serde::Serializer>(time: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(*time)
+}
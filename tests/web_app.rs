@@ -6,11 +6,72 @@
 
 use std::fs::read_to_string;
 
-use cargo_graphmod::app_builder::run_app;
+use cargo_graphmod::{
+    app_builder::run_app,
+    configuration::{Configuration, Granularity, ParserKind, TargetSelection},
+};
 
 #[test]
 fn it_generates_the_web_app_graph() {
-    let output = run_app("tests/web_app/src", "web_app");
+    let output = run_app("tests/web_app/src", "web_app", &Configuration::default());
     let golden_master = read_to_string("tests/web_app/modules.dot").unwrap();
     assert_eq!(output.trim(), golden_master.trim());
+}
+
+#[test]
+fn it_reads_the_crate_name_from_the_manifest() {
+    let output = run_app(
+        "tests/web_app/src",
+        "web-app-main",
+        &Configuration::default(),
+    );
+    let golden_master = read_to_string("tests/web_app/modules.dot").unwrap();
+    assert_eq!(output.trim(), golden_master.trim());
+}
+
+#[test]
+fn it_generates_the_web_app_graph_with_syn() {
+    let configuration = Configuration {
+        parser: ParserKind::Syn,
+        ..Configuration::default()
+    };
+    let output = run_app("tests/web_app/src", "web_app", &configuration);
+    let golden_master = read_to_string("tests/web_app/modules_syn.dot").unwrap();
+    assert_eq!(output.trim(), golden_master.trim());
+}
+
+#[test]
+fn it_generates_the_web_app_graph_of_items() {
+    let configuration = Configuration {
+        parser: ParserKind::Syn,
+        granularity: Granularity::Item,
+        ..Configuration::default()
+    };
+    let output = run_app("tests/web_app/src", "web_app", &configuration);
+    let golden_master = read_to_string("tests/web_app/modules_items.dot").unwrap();
+    assert_eq!(output.trim(), golden_master.trim());
+}
+
+#[test]
+fn it_generates_the_web_app_graph_with_spans() {
+    let configuration = Configuration {
+        parser: ParserKind::Syn,
+        spans: true,
+        ..Configuration::default()
+    };
+    let output = run_app("tests/web_app/src", "web_app", &configuration);
+    let golden_master = read_to_string("tests/web_app/modules_spans.dot").unwrap();
+    assert_eq!(output.trim(), golden_master.trim());
+}
+
+#[test]
+fn it_generates_the_web_app_graph_of_all_targets() {
+    let configuration = Configuration {
+        parser: ParserKind::Syn,
+        targets: Some("lib,bin,example,test".parse::<TargetSelection>().unwrap()),
+        ..Configuration::default()
+    };
+    let output = run_app("tests/web_app/src", "web_app", &configuration);
+    let golden_master = read_to_string("tests/web_app/modules_targets.dot").unwrap();
+    assert_eq!(output.trim(), golden_master.trim());
 }
\ No newline at end of file
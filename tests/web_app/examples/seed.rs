@@ -0,0 +1,5 @@
+use web_app::app_builder::run_app;
+
+fn main() {
+    run_app();
+}
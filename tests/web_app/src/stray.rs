@@ -0,0 +1,3 @@
+use crate::domain::Domain;
+
+pub fn forgotten(_domain: Domain) {}
@@ -0,0 +1,9 @@
+mod helpers;
+
+use web_app::app_builder::run_app;
+
+#[test]
+fn it_runs_the_app() {
+    helpers::setup();
+    run_app();
+}
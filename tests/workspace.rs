@@ -0,0 +1,31 @@
+/**
+ * Copyright 2023 Thomas Hügel.
+ * This file is part of Cargo Graphmod.
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+use std::{fs::read_to_string, path::PathBuf};
+
+use cargo_graphmod::{
+    app_builder::run_workspace,
+    configuration::{Configuration, MetadataSource},
+};
+
+#[test]
+fn it_generates_the_workspace_graph() {
+    let output = run_workspace("tests/workspace", &Configuration::default());
+    let golden_master = read_to_string("tests/workspace/modules.dot").unwrap();
+    assert_eq!(output.trim(), golden_master.trim());
+}
+
+#[test]
+fn it_leaves_out_the_unresolved_imports_given_the_metadata() {
+    let configuration = Configuration {
+        metadata: Some(MetadataSource::File(PathBuf::from(
+            "tests/workspace/metadata.json",
+        ))),
+        ..Configuration::default()
+    };
+    let output = run_workspace("tests/workspace", &configuration);
+    let golden_master = read_to_string("tests/workspace/modules_metadata.dot").unwrap();
+    assert_eq!(output.trim(), golden_master.trim());
+}
@@ -0,0 +1,11 @@
+use shop_domain::{customer::Customer, order::Order};
+use shop_legacy::archive;
+use shop_storage::memory::Memory;
+
+fn main() {
+    let mut memory = Memory::default();
+    let customer = Customer {
+        name: String::from("Ada"),
+    };
+    memory.add(Order { customer });
+}
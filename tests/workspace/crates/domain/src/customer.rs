@@ -0,0 +1,3 @@
+pub struct Customer {
+    pub name: String,
+}
@@ -0,0 +1,5 @@
+use crate::customer::Customer;
+
+pub struct Order {
+    pub customer: Customer,
+}
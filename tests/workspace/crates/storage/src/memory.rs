@@ -0,0 +1,12 @@
+use model::order::Order;
+
+#[derive(Default)]
+pub struct Memory {
+    orders: Vec<Order>,
+}
+
+impl Memory {
+    pub fn add(&mut self, order: Order) {
+        self.orders.push(order);
+    }
+}